@@ -0,0 +1,625 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    env,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rusqlite::{Connection, OpenFlags};
+
+use crate::{
+    app::{Card, Deck, Guess, ReviewMode},
+    cloze::render_cloze,
+    review_log::ReviewLogEntry,
+    scheduler::{ReviewState, SchedulerKind, LEITNER_INTERVALS},
+    typed_answer::AnswerOptions,
+};
+
+/// the collection files an Anki package can contain, newest format first
+const COLLECTION_FILES: [&str; 3] = ["collection.anki21b", "collection.anki21", "collection.anki2"];
+
+/// the decks and review history read from an Anki package
+pub struct AnkiImport {
+    pub decks: Vec<Deck>,
+    pub review_log: Vec<ReviewLogEntry>,
+}
+
+/// a note type with the field names and card templates needed to render its cards
+struct NoteType {
+    is_cloze: bool,
+    fields: Vec<String>,
+    templates: HashMap<u32, (String, String)>, // question and answer format by template ordinal
+}
+
+struct Note {
+    note_type: i64,
+    fields: Vec<String>,
+    tags: BTreeSet<String>,
+}
+
+/// reads the notes, cards, decks and review log of an `.apkg` or `.colpkg` file
+pub fn import_package(path: &Path) -> io::Result<AnkiImport> {
+    let collection = extract_collection(path)?;
+    let result = read_collection(&collection).map_err(io::Error::other);
+    let _ = fs::remove_file(&collection);
+    result
+}
+
+/// unpacks the collection database of the package into a temporary file, sqlite needs a path to open
+fn extract_collection(path: &Path) -> io::Result<PathBuf> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?).map_err(io::Error::other)?;
+
+    let Some(name) = COLLECTION_FILES.iter().find(|name| archive.by_name(name).is_ok()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} does not contain an Anki collection", path.display()),
+        ));
+    };
+
+    let mut contents = Vec::new();
+    archive.by_name(name).map_err(io::Error::other)?.read_to_end(&mut contents)?;
+    if *name == "collection.anki21b" {
+        contents = zstd::decode_all(contents.as_slice())?;
+    }
+
+    let collection = env::temp_dir().join(format!("ok-card-import-{}.anki2", rand::random::<u64>()));
+    fs::write(&collection, contents)?;
+    Ok(collection)
+}
+
+fn read_collection(path: &Path) -> rusqlite::Result<AnkiImport> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let now = Utc::now();
+
+    let collection_created: i64 = connection.query_row("SELECT crt FROM col", [], |row| row.get(0))?;
+    let collection_created = Utc.timestamp_opt(collection_created, 0).single().unwrap_or(now);
+
+    let has_separate_tables: bool = connection.query_row(
+        "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'notetypes'",
+        [],
+        |row| row.get::<_, i64>(0).map(|count| count > 0),
+    )?;
+    let (deck_names, note_types) = if has_separate_tables {
+        (read_decks(&connection)?, read_note_types(&connection)?)
+    } else {
+        read_legacy_decks_and_note_types(&connection)?
+    };
+
+    let mut notes = HashMap::new();
+    let mut statement = connection.prepare("SELECT id, mid, flds, tags FROM notes")?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let fields: String = row.get(2)?;
+        let tags: String = row.get(3)?;
+        notes.insert(
+            row.get::<_, i64>(0)?,
+            Note {
+                note_type: row.get(1)?,
+                fields: fields.split('\u{1f}').map(String::from).collect(),
+                tags: tags.split_whitespace().map(String::from).collect(),
+            },
+        );
+    }
+
+    let mut last_reviews: HashMap<i64, DateTime<Utc>> = HashMap::new();
+    let mut review_log = Vec::new();
+    let mut card_decks = HashMap::new();
+    let mut decks: HashMap<i64, Deck> = HashMap::new();
+
+    let mut statement = connection.prepare(
+        "SELECT id, nid, did, ord, type, queue, due, ivl, factor, reps, lapses, odid, odue FROM cards ORDER BY did, due",
+    )?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let card_id: i64 = row.get(0)?;
+        let Some(note) = notes.get(&row.get::<_, i64>(1)?) else {
+            continue;
+        };
+        let Some(note_type) = note_types.get(&note.note_type) else {
+            continue;
+        };
+        // cards in filtered decks remember where they came from
+        let original_deck: i64 = row.get(11)?;
+        let deck_id = if original_deck != 0 { original_deck } else { row.get(2)? };
+        let due: i64 = if original_deck != 0 { row.get(12)? } else { row.get(6)? };
+        let queue: i64 = row.get(5)?;
+
+        let Some((front, back)) = render_card(note_type, note, row.get(3)?) else {
+            continue;
+        };
+
+        let mut card = Card::new(front, back);
+        card.id = card_id as u64;
+        // anki card ids are the time the card was created in milliseconds
+        card.created = Utc.timestamp_millis_opt(card_id).single();
+        card.tags = note.tags.clone();
+        card.review = review_state(
+            row.get(4)?,
+            queue,
+            due,
+            row.get(7)?,
+            row.get(8)?,
+            row.get(9)?,
+            row.get(10)?,
+            collection_created,
+            now,
+        );
+        // suspended cards sit in queue -1 and keep their scheduling for when they are unsuspended
+        card.suspended = queue == -1;
+
+        card_decks.insert(card_id, deck_id);
+        decks
+            .entry(deck_id)
+            .or_insert_with(|| Deck {
+                id: deck_id as u64,
+                name: deck_names.get(&deck_id).cloned().unwrap_or_else(|| format!("Anki deck {deck_id}")),
+                cards: Vec::new(),
+                date_last_learned: collection_created,
+                scheduler: SchedulerKind::default(),
+                review_mode: ReviewMode::default(),
+                answer_options: AnswerOptions::default(),
+                collapsed: false,
+            })
+            .cards
+            .push(card);
+    }
+
+    let mut statement = connection.prepare("SELECT id, cid, ease, ivl, lastIvl, time FROM revlog ORDER BY id")?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let card_id: i64 = row.get(1)?;
+        let Some(deck_id) = card_decks.get(&card_id) else {
+            continue;
+        };
+        let Some(timestamp) = Utc.timestamp_millis_opt(row.get(0)?).single() else {
+            continue;
+        };
+        let grade = match row.get::<_, i64>(2)? {
+            1 => Guess::Incorrect,
+            2 | 3 => Guess::Correct,
+            4 => Guess::Easy,
+            // manual rescheduling, not an answer
+            _ => continue,
+        };
+        review_log.push(ReviewLogEntry {
+            card_id: card_id as u64,
+            deck_id: *deck_id as u64,
+            timestamp,
+            grade,
+            previous_interval: interval_days(row.get(4)?),
+            new_interval: interval_days(row.get(3)?),
+            time_spent_ms: row.get::<_, i64>(5)?.max(0) as u64,
+        });
+        last_reviews.insert(card_id, timestamp);
+
+        if let Some(deck) = decks.get_mut(deck_id) {
+            deck.date_last_learned = deck.date_last_learned.max(timestamp);
+        }
+    }
+
+    let mut decks: Vec<Deck> = decks.into_values().collect();
+    decks.sort_by(|a, b| a.name.cmp(&b.name));
+    for card in decks.iter_mut().flat_map(|deck| deck.cards.iter_mut()) {
+        if let Some(last_review) = last_reviews.get(&(card.id as i64)) {
+            card.review.last_review = Some(*last_review);
+        }
+    }
+
+    Ok(AnkiImport { decks, review_log })
+}
+
+/// maps the scheduling columns of an Anki card onto the review state used by our schedulers
+#[allow(clippy::too_many_arguments)]
+fn review_state(
+    card_type: i64,
+    queue: i64,
+    due: i64,
+    interval: i64,
+    factor: i64,
+    reviews: i64,
+    lapses: i64,
+    collection_created: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> ReviewState {
+    let mut state = ReviewState::new(now);
+    // new cards are due straight away, their `due` is only a position in the queue
+    if card_type == 0 {
+        return state;
+    }
+
+    state.interval = interval_days(interval);
+    if factor > 0 {
+        state.ease_factor = (factor as f64 / 1000.0).max(1.3);
+    }
+    state.lapses = lapses.max(0) as u32;
+    state.repetitions = (reviews - lapses).max(1) as u32;
+    state.leitner_box = LEITNER_INTERVALS
+        .iter()
+        .position(|days| *days >= state.interval)
+        .unwrap_or(LEITNER_INTERVALS.len() - 1) as u8
+        + 1;
+
+    // cards in the learning queue are due at a timestamp, review cards on a day counted from the collection's creation
+    state.due = if queue == 1 {
+        Utc.timestamp_opt(due, 0).single().unwrap_or(now)
+    } else {
+        collection_created + Duration::days(due)
+    };
+    state.last_review = Some(state.due - Duration::days(state.interval as i64));
+    state
+}
+
+/// Anki stores intervals of learning cards as negative seconds
+fn interval_days(interval: i64) -> u32 {
+    if interval < 0 {
+        0
+    } else {
+        interval as u32
+    }
+}
+
+/// deck and note type definitions of collections before schema 18, stored as json in the `col` table
+#[allow(clippy::type_complexity)]
+fn read_legacy_decks_and_note_types(
+    connection: &Connection,
+) -> rusqlite::Result<(HashMap<i64, String>, HashMap<i64, NoteType>)> {
+    let (decks, models): (String, String) =
+        connection.query_row("SELECT decks, models FROM col", [], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    let decks: serde_json::Value = serde_json::from_str(&decks).unwrap_or_default();
+    let models: serde_json::Value = serde_json::from_str(&models).unwrap_or_default();
+
+    let deck_names = decks
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(id, deck)| Some((id.parse().ok()?, deck["name"].as_str()?.to_string())))
+        .collect();
+
+    let note_types = models
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(id, model)| {
+            let mut fields: Vec<(u64, String)> = model["flds"]
+                .as_array()?
+                .iter()
+                .filter_map(|field| Some((field["ord"].as_u64()?, field["name"].as_str()?.to_string())))
+                .collect();
+            fields.sort();
+            let templates = model["tmpls"]
+                .as_array()?
+                .iter()
+                .filter_map(|template| {
+                    Some((
+                        template["ord"].as_u64()? as u32,
+                        (template["qfmt"].as_str()?.to_string(), template["afmt"].as_str()?.to_string()),
+                    ))
+                })
+                .collect();
+            let note_type = NoteType {
+                is_cloze: model["type"].as_i64() == Some(1),
+                fields: fields.into_iter().map(|(_, name)| name).collect(),
+                templates,
+            };
+            Some((id.parse().ok()?, note_type))
+        })
+        .collect();
+
+    Ok((deck_names, note_types))
+}
+
+/// deck names of schema 18 collections, nested decks are separated by `\x1f` instead of `::`
+fn read_decks(connection: &Connection) -> rusqlite::Result<HashMap<i64, String>> {
+    let mut statement = connection.prepare("SELECT id, name FROM decks")?;
+    let deck_names = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get::<_, String>(1)?.replace('\u{1f}', "::"))))?
+        .collect();
+    deck_names
+}
+
+/// note types of schema 18 collections, with their configuration stored as protobuf messages
+fn read_note_types(connection: &Connection) -> rusqlite::Result<HashMap<i64, NoteType>> {
+    let mut note_types = HashMap::new();
+
+    let mut statement = connection.prepare("SELECT id, config FROM notetypes")?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let config: Vec<u8> = row.get(1)?;
+        // `kind` is the first field of the notetype config, 1 being cloze
+        let is_cloze = matches!(protobuf_field(&config, 1), Some(ProtobufValue::Varint(1)));
+        note_types.insert(
+            row.get(0)?,
+            NoteType { is_cloze, fields: Vec::new(), templates: HashMap::new() },
+        );
+    }
+
+    let mut statement = connection.prepare("SELECT ntid, name FROM fields ORDER BY ntid, ord")?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        if let Some(note_type) = note_types.get_mut(&row.get(0)?) {
+            note_type.fields.push(row.get(1)?);
+        }
+    }
+
+    let mut statement = connection.prepare("SELECT ntid, ord, config FROM templates")?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let config: Vec<u8> = row.get(2)?;
+        // the question and answer formats are the first two fields of the template config
+        let format = |field| match protobuf_field(&config, field) {
+            Some(ProtobufValue::Bytes(bytes)) => String::from_utf8_lossy(bytes).into_owned(),
+            _ => String::new(),
+        };
+        if let Some(note_type) = note_types.get_mut(&row.get(0)?) {
+            note_type.templates.insert(row.get(1)?, (format(1), format(2)));
+        }
+    }
+
+    Ok(note_types)
+}
+
+enum ProtobufValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+/// finds the first occurrence of `wanted` in a protobuf message, enough to read a few known fields
+fn protobuf_field(mut bytes: &[u8], wanted: u64) -> Option<ProtobufValue<'_>> {
+    fn varint(bytes: &mut &[u8]) -> Option<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let (byte, rest) = bytes.split_first()?;
+            *bytes = rest;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    while !bytes.is_empty() {
+        let key = varint(&mut bytes)?;
+        let value = match key & 7 {
+            0 => ProtobufValue::Varint(varint(&mut bytes)?),
+            1 | 5 => {
+                let len = if key & 7 == 1 { 8 } else { 4 };
+                let (value, rest) = bytes.split_at_checked(len)?;
+                bytes = rest;
+                ProtobufValue::Bytes(value)
+            }
+            2 => {
+                let len = varint(&mut bytes)? as usize;
+                let (value, rest) = bytes.split_at_checked(len)?;
+                bytes = rest;
+                ProtobufValue::Bytes(value)
+            }
+            _ => return None,
+        };
+        if key >> 3 == wanted {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// the front and back of the card generated by template `ordinal` of a note, as plain text
+fn render_card(note_type: &NoteType, note: &Note, ordinal: u32) -> Option<(String, String)> {
+    let fields: HashMap<&str, &str> = note_type
+        .fields
+        .iter()
+        .map(String::as_str)
+        .zip(note.fields.iter().map(String::as_str))
+        .collect();
+
+    // cloze note types have a single template, the ordinal picks the cloze number instead
+    let (template_ordinal, cloze) = if note_type.is_cloze { (0, Some(ordinal + 1)) } else { (ordinal, None) };
+    let (question_format, answer_format) = note_type.templates.get(&template_ordinal)?;
+
+    let question = render_template(question_format, &fields, cloze, false, "");
+    let answer = render_template(answer_format, &fields, cloze, true, &question);
+    // the answer usually repeats the question above a `<hr id=answer>` line
+    let answer = match answer.find("<hr id=answer>") {
+        Some(position) => answer[position + "<hr id=answer>".len()..].to_string(),
+        None => answer.strip_prefix(question.as_str()).map(String::from).unwrap_or(answer),
+    };
+
+    let (front, back) = (html_to_text(&question), html_to_text(&answer));
+    (!front.is_empty()).then_some((front, back))
+}
+
+/// fills in the `{{field}}` references of an Anki card template.
+/// Conditional sections are kept or dropped depending on whether their field is empty.
+fn render_template(
+    template: &str,
+    fields: &HashMap<&str, &str>,
+    cloze: Option<u32>,
+    reveal: bool,
+    front_side: &str,
+) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = rest[start + 2..start + end].trim();
+        rest = &rest[start + end + 2..];
+
+        if let Some(section) = tag.strip_prefix('#').or_else(|| tag.strip_prefix('^')) {
+            let closing = format!("{{{{/{section}}}}}");
+            let (inner, after) = rest.split_once(closing.as_str()).unwrap_or((rest, ""));
+            let is_filled = fields.get(section).is_some_and(|value| !value.trim().is_empty());
+            if is_filled == tag.starts_with('#') {
+                output.push_str(&render_template(inner, fields, cloze, reveal, front_side));
+            }
+            rest = after;
+            continue;
+        }
+
+        if tag == "FrontSide" {
+            output.push_str(front_side);
+            continue;
+        }
+
+        // filters like `text:` or `cloze:` come before the field name
+        let (filters, field) = tag.rsplit_once(':').unwrap_or(("", tag));
+        if filters.contains("type") {
+            continue;
+        }
+        let value = fields.get(field.trim()).copied().unwrap_or_default();
+        match cloze {
+            Some(active) if filters.contains("cloze") => output.push_str(&render_cloze(value, active, reveal)),
+            _ => output.push_str(value),
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// strips the html Anki stores fields as, keeping line breaks
+fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_lowercase();
+        if tag.starts_with("br") || tag.starts_with("/div") || tag.starts_with("/p") || tag.starts_with("hr") {
+            text.push('\n');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protobuf_fields() {
+        // field 1 varint 1, field 2 bytes "hi", field 3 varint 300, field 4 fixed32
+        let message = [0x08, 0x01, 0x12, 0x02, b'h', b'i', 0x18, 0xac, 0x02, 0x25, 1, 2, 3, 4];
+        assert!(matches!(protobuf_field(&message, 1), Some(ProtobufValue::Varint(1))));
+        assert!(matches!(protobuf_field(&message, 2), Some(ProtobufValue::Bytes(b"hi"))));
+        assert!(matches!(protobuf_field(&message, 3), Some(ProtobufValue::Varint(300))));
+        assert!(matches!(protobuf_field(&message, 4), Some(ProtobufValue::Bytes(&[1, 2, 3, 4]))));
+        assert!(protobuf_field(&message, 5).is_none());
+        // a length running past the end of the message
+        assert!(protobuf_field(&[0x12, 0x05, b'h'], 2).is_none());
+    }
+
+    #[test]
+    fn templates() {
+        let fields = HashMap::from([("Front", "hola"), ("Back", "hello"), ("Hint", ""), ("Text", "{{c1::Madrid}} is in {{c2::Spain}}")]);
+        let cases = [
+            ("{{Front}}", None, false, "hola"),
+            ("{{ Front }} / {{text:Back}}", None, false, "hola / hello"),
+            ("{{Front}}{{#Hint}} ({{Hint}}){{/Hint}}", None, false, "hola"),
+            ("{{Front}}{{^Hint}} (no hint){{/Hint}}", None, false, "hola (no hint)"),
+            ("{{FrontSide}}<hr id=answer>{{Back}}", None, true, "Q<hr id=answer>hello"),
+            ("{{Front}}{{type:Back}}", None, false, "hola"),
+            ("{{cloze:Text}}", Some(1), false, "[...] is in Spain"),
+            ("{{cloze:Text}}", Some(2), true, "Madrid is in Spain"),
+            ("{{Missing}}!", None, false, "!"),
+        ];
+        for (template, cloze, reveal, rendered) in cases {
+            assert_eq!(render_template(template, &fields, cloze, reveal, "Q"), rendered, "{template}");
+        }
+    }
+
+    #[test]
+    fn html() {
+        let cases = [
+            ("plain", "plain"),
+            ("<b>bold</b> text", "bold text"),
+            ("one<br>two<br/>three", "one\ntwo\nthree"),
+            ("<div>a</div><div>b</div>", "a\nb"),
+            ("&lt;tag&gt; &amp;&nbsp;&quot;x&quot; &#39;y&#39;", "<tag> & \"x\" 'y'"),
+            ("  spaced  <br><br>  out ", "spaced\nout"),
+        ];
+        for (html, text) in cases {
+            assert_eq!(html_to_text(html), text, "{html}");
+        }
+    }
+
+    #[test]
+    fn cards_from_notes() {
+        let basic = NoteType {
+            is_cloze: false,
+            fields: vec![String::from("Front"), String::from("Back")],
+            templates: HashMap::from([
+                (0, (String::from("{{Front}}"), String::from("{{FrontSide}}<hr id=answer>{{Back}}"))),
+                (1, (String::from("{{Back}}"), String::from("{{FrontSide}}<hr id=answer>{{Front}}"))),
+            ]),
+        };
+        let note = Note { note_type: 1, fields: vec![String::from("hola"), String::from("<i>hello</i>")], tags: BTreeSet::new() };
+        assert_eq!(render_card(&basic, &note, 0), Some((String::from("hola"), String::from("hello"))));
+        assert_eq!(render_card(&basic, &note, 1), Some((String::from("hello"), String::from("hola"))));
+        assert_eq!(render_card(&basic, &note, 2), None);
+
+        let cloze = NoteType {
+            is_cloze: true,
+            fields: vec![String::from("Text")],
+            templates: HashMap::from([(0, (String::from("{{cloze:Text}}"), String::from("{{cloze:Text}}")))]),
+        };
+        let note = Note { note_type: 2, fields: vec![String::from("{{c1::Madrid}} is in {{c2::Spain::country}}")], tags: BTreeSet::new() };
+        assert_eq!(render_card(&cloze, &note, 1), Some((String::from("Madrid is in [country]"), String::from("Madrid is in Spain"))));
+    }
+
+    #[test]
+    fn suspended_cards() {
+        let path = env::temp_dir().join(format!("ok-card-test-{}.anki2", rand::random::<u64>()));
+        let connection = Connection::open(&path).unwrap();
+        let models = r#"{"1": {"type": 0, "flds": [{"ord": 0, "name": "Front"}, {"ord": 1, "name": "Back"}],
+            "tmpls": [{"ord": 0, "qfmt": "{{Front}}", "afmt": "{{Back}}"}]}}"#;
+        connection
+            .execute_batch(
+                "CREATE TABLE col (crt INTEGER, decks TEXT, models TEXT);
+                CREATE TABLE notes (id INTEGER, mid INTEGER, flds TEXT, tags TEXT);
+                CREATE TABLE cards (id INTEGER, nid INTEGER, did INTEGER, ord INTEGER, type INTEGER, queue INTEGER, due INTEGER,
+                    ivl INTEGER, factor INTEGER, reps INTEGER, lapses INTEGER, odid INTEGER, odue INTEGER);
+                CREATE TABLE revlog (id INTEGER, cid INTEGER, ease INTEGER, ivl INTEGER, lastIvl INTEGER, time INTEGER);
+                INSERT INTO notes VALUES (1, 1, 'hola\x1fhello', ''), (2, 1, 'adiós\x1fgoodbye', '');
+                -- a review card that was suspended and a new one
+                INSERT INTO cards VALUES (10, 1, 1, 0, 2, -1, 30, 10, 2500, 3, 0, 0, 0), (20, 2, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0);",
+            )
+            .unwrap();
+        connection
+            .execute("INSERT INTO col VALUES (0, ?1, ?2)", [r#"{"1": {"name": "Spanish"}}"#, models])
+            .unwrap();
+        drop(connection);
+
+        let import = read_collection(&path);
+        let _ = fs::remove_file(&path);
+        let cards: Vec<(String, bool)> =
+            import.unwrap().decks[0].cards.iter().map(|card| (card.front.clone(), card.suspended)).collect();
+        // cards come in the order they are due, the new card first
+        assert_eq!(cards, [(String::from("adiós"), false), (String::from("hola"), true)]);
+    }
+
+    #[test]
+    fn intervals() {
+        assert_eq!(interval_days(-600), 0);
+        assert_eq!(interval_days(12), 12);
+    }
+}
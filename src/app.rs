@@ -1,23 +1,82 @@
-use serde_json::Result;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    env, io,
+    path::PathBuf,
+    time::Instant,
+};
 use chrono::{DateTime, Utc};
-use rand::Rng;
 
+use crate::{
+    anki_import::{self, AnkiImport},
+    browser::{Browser, BrowserColumn, BulkAction},
+    cloze::{cloze_answers, cloze_indices, render_cloze},
+    csv_import::{CsvImport, ImportTarget},
+    deck_tree,
+    external_editor::{self, EditedNote},
+    history::{History, Snapshot},
+    keymap::{KeyContext, Keymap},
+    multiple_choice::MultipleChoice,
+    query::{self, Query, QueryError},
+    review_log::ReviewLogEntry,
+    scheduler::{ReviewState, Scheduler, SchedulerKind},
+    search::{self, SearchResult},
+    stats::Statistics,
+    storage,
+    text_input::TextInput,
+    theme::{self, ColorScheme, ColorSupport, Theme},
+    typed_answer::{check_answer, AnswerCheck, AnswerOptions},
+};
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum CurrentScreen {
     Main,
     AddingDeck,
     ViewingDeck,
     EditingCard,
+    ConfirmingDelete,
+    EnteringImportPath,
+    ImportPreview,
+    FilteringTags,
+    Searching,
+    EnteringQuery,
+    Browsing,
     LearningMode,
+    Statistics,
+    PickingTheme,
     Exiting,
 }
 
+/// what happens to the card once it comes back from `$EDITOR`
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExternalEdit {
+    Fields,   // fills the fields of the open card editor
+    SaveCard, // saves the card right away, the card editor was only opened to hold it
+}
+
+/// what is deleted once the confirmation popup is accepted
 #[derive(Clone, Copy)]
+pub enum DeleteTarget {
+    Deck(usize),
+    Card(usize),
+    BrowserCards, // the cards marked in the card browser
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum CardFace {
     CardFront,
     CardBack,
 }
 
+/// the field of the card editor being typed in
+#[derive(Clone, Copy, PartialEq)]
+pub enum EditorField {
+    Front,
+    Back,
+    Tags,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
 pub enum Guess {
     Easy,
     Correct,
@@ -25,33 +84,360 @@ pub enum Guess {
     None,
 }
 
+/// which side of a note a card asks about
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum CardKind {
+    #[default]
+    Basic,
+    Reverse, // asks for the back and expects the front
+    Cloze(u32), // asks for the deletions with this number in the front text
+}
+
+/// which way round the cards of a basic note are asked, chosen in the card editor
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum CardDirection {
+    #[default]
+    FrontToBack,
+    BackToFront,
+    Both, // one card for each direction, scheduled independently
+}
+
+/// how the cards of a deck are answered in learning mode
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum ReviewMode {
+    #[default]
+    Recall, // the answer is revealed and graded by the user
+    TypeAnswer, // the answer is typed in and compared with the back of the card before grading
+    MultipleChoice, // the answer is picked among the answers of other cards of the deck
+}
+
+impl ReviewMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReviewMode::Recall => "recall",
+            ReviewMode::TypeAnswer => "type the answer",
+            ReviewMode::MultipleChoice => "multiple choice",
+        }
+    }
+
+    pub fn next(&self) -> ReviewMode {
+        match self {
+            ReviewMode::Recall => ReviewMode::TypeAnswer,
+            ReviewMode::TypeAnswer => ReviewMode::MultipleChoice,
+            ReviewMode::MultipleChoice => ReviewMode::Recall,
+        }
+    }
+}
+
+impl CardDirection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CardDirection::FrontToBack => "front → back",
+            CardDirection::BackToFront => "back → front",
+            CardDirection::Both => "both directions",
+        }
+    }
+
+    pub fn next(&self) -> CardDirection {
+        match self {
+            CardDirection::FrontToBack => CardDirection::BackToFront,
+            CardDirection::BackToFront => CardDirection::Both,
+            CardDirection::Both => CardDirection::FrontToBack,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Card {
+    #[serde(default = "new_id")]
+    pub id: u64,
     pub front: String,
     pub back: String,
     pub last_guess: Guess, 
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    #[serde(default)]
+    pub kind: CardKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_id: Option<u64>, // cards generated from the same note share it, editing one of them updates all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>, // unknown for cards saved before it was recorded
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suspended: bool, // suspended cards are never due until they are unsuspended in the card browser
+    #[serde(flatten)]
+    pub review: ReviewState, // when the card is due and what the scheduler knows about it
+}
+
+/// a random identifier for cards and decks, so they can be referred to after being renamed or moved
+pub fn new_id() -> u64 {
+    rand::random()
 }
 
+/// the tags typed in the card editor, separated by spaces or commas
+pub fn parse_tags(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| c.is_whitespace() || c == ',').filter(|tag| !tag.is_empty()).map(String::from).collect()
+}
+
+impl Card {
+    pub fn new(front: String, back: String) -> Card {
+        Card {
+            id: new_id(),
+            front,
+            back,
+            last_guess: Guess::None,
+            tags: BTreeSet::new(),
+            kind: CardKind::Basic,
+            note_id: None,
+            created: Some(Utc::now()),
+            suspended: false,
+            review: ReviewState::new(Utc::now()),
+        }
+    }
+
+    /// the text shown before the answer is revealed
+    pub fn question(&self) -> String {
+        match self.kind {
+            CardKind::Basic => self.front.clone(),
+            CardKind::Reverse => self.back.clone(),
+            CardKind::Cloze(index) => render_cloze(&self.front, index, false),
+        }
+    }
+
+    /// the text shown once the answer is revealed, for cloze cards the back holds extra notes
+    pub fn answer(&self) -> String {
+        match self.kind {
+            CardKind::Basic => self.back.clone(),
+            CardKind::Reverse => self.front.clone(),
+            CardKind::Cloze(index) => {
+                let revealed = render_cloze(&self.front, index, true);
+                if self.back.is_empty() {
+                    revealed
+                } else {
+                    format!("{revealed}\n\n{}", self.back)
+                }
+            }
+        }
+    }
+
+    /// whether the card has one of `tags`, every card matches when no tags are given
+    pub fn has_any_tag(&self, tags: &BTreeSet<String>) -> bool {
+        tags.is_empty() || !self.tags.is_disjoint(tags)
+    }
+
+    /// what has to be typed in to answer the card
+    pub fn expected_answer(&self) -> String {
+        match self.kind {
+            CardKind::Basic => self.back.clone(),
+            CardKind::Reverse => self.front.clone(),
+            CardKind::Cloze(index) => cloze_answers(&self.front, index).join(", "),
+        }
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        !self.suspended && self.review.due <= now
+    }
+
+    /// updates the scheduling of the card after it was answered with `guess`
+    pub fn review(&mut self, guess: Guess, now: DateTime<Utc>, scheduler: &dyn Scheduler) {
+        if guess == Guess::None {
+            return;
+        }
+        self.review = scheduler.schedule(&self.review, guess, now);
+        self.last_guess = guess;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Deck { // one deck of cards with a name an a list of cards
+    #[serde(default = "new_id")]
+    pub id: u64,
     pub name: String,
     //declare a vector of cards
     pub cards: Vec<Card>,
     pub date_last_learned: DateTime<Utc>,
+    #[serde(default)]
+    pub scheduler: SchedulerKind, // the algorithm deciding when the cards of this deck are due
+    #[serde(default)]
+    pub review_mode: ReviewMode,
+    #[serde(default)]
+    pub answer_options: AnswerOptions, // how typed answers are compared in the type the answer mode
+    #[serde(default)]
+    pub collapsed: bool, // whether the decks nested in this one are hidden in the deck tree
+}
+
+/// the kinds of cards a note with this front generates, one per cloze number or one per direction of a basic note
+pub fn note_card_kinds(front: &str, direction: CardDirection) -> Vec<CardKind> {
+    let indices = cloze_indices(front);
+    if !indices.is_empty() {
+        return indices.into_iter().map(CardKind::Cloze).collect();
+    }
+    match direction {
+        CardDirection::FrontToBack => vec![CardKind::Basic],
+        CardDirection::BackToFront => vec![CardKind::Reverse],
+        CardDirection::Both => vec![CardKind::Basic, CardKind::Reverse],
+    }
+}
+
+impl Deck {
+    /// adds the cards generated by a note and returns how many there were
+    pub fn add_note(&mut self, front: String, back: String, tags: BTreeSet<String>, direction: CardDirection) -> usize {
+        let kinds = note_card_kinds(&front, direction);
+        let note_id = (kinds.len() > 1 || kinds[0] != CardKind::Basic).then(new_id);
+        for kind in &kinds {
+            let mut card = Card::new(front.clone(), back.clone());
+            card.kind = *kind;
+            card.tags = tags.clone();
+            card.note_id = note_id;
+            self.cards.push(card);
+        }
+        kinds.len()
+    }
+
+    /// changes the fields and tags of the note the card at `card_index` belongs to.
+    /// Sibling cards that are still generated keep their scheduling, new ones are added after them and
+    /// the ones that are no longer generated are removed. Returns the new index of the edited card.
+    pub fn update_note(
+        &mut self,
+        card_index: usize,
+        front: String,
+        back: String,
+        tags: BTreeSet<String>,
+        direction: CardDirection,
+    ) -> Option<usize> {
+        let edited = self.cards.get(card_index)?;
+        let edited_id = edited.id;
+        let note_id = edited.note_id;
+        let mut siblings: Vec<usize> = match note_id {
+            Some(note_id) => (0..self.cards.len()).filter(|index| self.cards[*index].note_id == Some(note_id)).collect(),
+            None => vec![card_index],
+        };
+        let insert_at = siblings.iter().max().copied().unwrap_or(card_index) + 1;
+
+        let kinds = note_card_kinds(&front, direction);
+        let generates_several = kinds.len() > 1 || kinds[0] != CardKind::Basic;
+        let mut missing = Vec::new();
+        for kind in kinds {
+            match siblings.iter().position(|index| self.cards[*index].kind == kind) {
+                Some(position) => {
+                    let index = siblings.remove(position);
+                    self.cards[index].front = front.clone();
+                    self.cards[index].back = back.clone();
+                    self.cards[index].tags = tags.clone();
+                }
+                None => missing.push(kind),
+            }
+        }
+
+        // siblings left over are turned into the missing cards, the edited card first so it keeps its scheduling
+        siblings.sort_by_key(|index| self.cards[*index].id != edited_id);
+        let mut new_cards = Vec::new();
+        for kind in missing {
+            if siblings.is_empty() {
+                let mut card = Card::new(front.clone(), back.clone());
+                card.kind = kind;
+                card.tags = tags.clone();
+                new_cards.push(card);
+            } else {
+                let card = &mut self.cards[siblings.remove(0)];
+                card.kind = kind;
+                card.front = front.clone();
+                card.back = back.clone();
+                card.tags = tags.clone();
+            }
+        }
+
+        let removed: Vec<u64> = siblings.iter().map(|index| self.cards[*index].id).collect();
+        let note_id = note_id.unwrap_or_else(new_id);
+        if generates_several {
+            self.cards[card_index].note_id = Some(note_id);
+            for card in new_cards.iter_mut() {
+                card.note_id = Some(note_id);
+            }
+        }
+        self.cards.splice(insert_at..insert_at, new_cards);
+        self.cards.retain(|card| !removed.contains(&card.id));
+
+        self.cards
+            .iter()
+            .position(|card| card.id == edited_id)
+            .or_else(|| self.cards.iter().position(|card| card.note_id == Some(note_id)))
+    }
+
+    /// every tag used in the deck with the number of cards that have it
+    pub fn tag_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for tag in self.cards.iter().flat_map(|card| card.tags.iter()) {
+            *counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// the direction the note of the card at `card_index` is asked in, worked out from the cards it generated
+    pub fn note_direction(&self, card_index: usize) -> CardDirection {
+        let Some(card) = self.cards.get(card_index) else {
+            return CardDirection::default();
+        };
+        let kinds: Vec<CardKind> = match card.note_id {
+            Some(note_id) => self.cards.iter().filter(|card| card.note_id == Some(note_id)).map(|card| card.kind).collect(),
+            None => vec![card.kind],
+        };
+        match (kinds.contains(&CardKind::Basic), kinds.contains(&CardKind::Reverse)) {
+            (true, true) => CardDirection::Both,
+            (false, true) => CardDirection::BackToFront,
+            _ => CardDirection::FrontToBack,
+        }
+    }
 }
 
 pub struct App {
     pub selected_index: Option<usize>, // the currently selected index of the list of decks.
     pub selected_card_index: Option<usize>, // the currently selected index of the list of cards.
-    pub name_input: String, // the currently being edited deck name.
-    pub front_input: String, // the currently being edited card front.
-    pub back_input: String,
+    pub name_input: TextInput, // the currently being edited deck name.
+    pub front_input: TextInput, // the currently being edited card front.
+    pub back_input: TextInput,
+    pub tags_input: TextInput, // the tags of the card being edited, separated by spaces
+    pub card_direction: CardDirection, // which way round the card in the editor is asked
     pub decks: Vec<Deck>, // The different decks of cards
-    pub pairs: HashMap<String, String>, // The representation of our key and value pairs with serde Serialize support
     pub current_screen: CurrentScreen, // the current screen the user is looking at, and will later determine what is rendered.
-    pub card_currently_editing: Option<CardFace>, // the optional state containing which of the card's front or back the user is editing. It is an option, because when the user is not directly editing a card, this will be set to `None`.
+    pub card_currently_editing: Option<EditorField>, // the optional state containing which of the card's front or back the user is editing. It is an option, because when the user is not directly editing a card, this will be set to `None`.
     pub adding_deck: bool, // the boolean state containing whether the user is adding a deck or not.
+    pub deck_being_renamed: Option<usize>, // the deck whose name is being edited in the deck name popup, `None` when adding a new deck
+    pub pending_delete: Option<DeleteTarget>, // the deck or card waiting for the user to confirm its deletion
     pub display_decks: bool, // the boolean state containing whether the user is displaying a deck or not.
-    pub card_currently_learning: Option<usize>,
+    pub card_currently_learning: Option<(usize, usize)>, // the deck and card index of the card being learned
+    pub card_being_edited: Option<(usize, usize)>, // the deck and card index of the existing card open in the editor, `None` when a new card is being added
+    pub return_screen: CurrentScreen, // the screen to go back to once a popup like the card editor is closed
     pub face_showing: Option<CardFace>,
+    pub tag_filter: BTreeSet<String>, // only cards with one of these tags are listed and learned, all cards when empty
+    pub tag_filter_index: usize, // the tag selected in the tag filter popup
+    pub search_input: TextInput, // the query typed in the search overlay
+    pub search_results: Vec<SearchResult>, // the cards matching the query, best match first
+    pub search_index: usize, // the result selected in the search overlay
+    pub query_input: TextInput, // the query picking the cards of a custom study session
+    pub query_matches: Vec<(usize, usize)>, // the deck and card index of every card matching the query
+    pub query_error: Option<QueryError>, // why the query can't be parsed
+    pub custom_study: Option<Vec<u64>>, // the ids of the cards left in a custom study session, learned in this order instead of the due cards
+    pub custom_study_return: CurrentScreen, // the deck list or deck view a custom study session was started from
+    pub browser: Option<Browser>, // the card browser, kept while it is open
+    pub external_edit: Option<ExternalEdit>, // set when the card in the editor should be opened in `$EDITOR` before the next frame
+    pub card_shown_at: Option<Instant>, // when the card being learned was shown, to measure the time spent on it
+    pub answer_input: TextInput, // the answer being typed in learning mode when the deck asks for typed answers
+    pub multiple_choice: Option<MultipleChoice>, // the answers offered in learning mode when the deck is in the multiple choice mode
+    pub review_log: Vec<ReviewLogEntry>, // every answer given in learning mode or imported, in the order it was added
+    pub history: History, // the changes to the decks and the review log that can be undone
+    pub keymap: Keymap, // which keys do what, loaded from the config directory
+    pub themes: Vec<Theme>, // the presets followed by the themes from the config directory
+    pub theme_index: usize, // the theme the interface is drawn with, previewed while picking one
+    pub theme_before_picking: usize, // the theme to go back to when the theme picker is cancelled
+    pub color_support: ColorSupport, // how many colours the terminal shows, the theme is adapted to it
+    pub config_dir: Option<PathBuf>, // where the picked theme is saved to, `None` keeps it in memory
+    pub path_input: TextInput, // the path of the file to import being typed in
+    pub csv_import: Option<CsvImport>, // the file shown in the import preview
+    pub import_error: Option<String>, // why the typed in file could not be opened
+    pub status_message: Option<String>, // the outcome of the last action, shown in the footer until the next key is pressed
+    pub export_all_decks: bool, // whether the exit screen exports every deck or only the selected one
+    pub statistics_deck: Option<usize>, // the deck the statistics screen is showing, `None` for all decks
+    pub collection_path: Option<PathBuf>, // where the decks are saved to, `None` keeps everything in memory
 }
 
 impl App {
@@ -59,56 +445,1414 @@ impl App {
         App {
             selected_index: None,
             selected_card_index: None,
-            name_input: String::new(),
-            front_input: String::new(),
-            back_input: String::new(),
+            name_input: TextInput::single_line(),
+            front_input: TextInput::multiline(),
+            back_input: TextInput::multiline(),
+            tags_input: TextInput::single_line(),
+            card_direction: CardDirection::default(),
             decks: Vec::new(),
-            pairs: HashMap::new(),
             current_screen: CurrentScreen::Main,
             card_currently_editing: None,
             adding_deck: false,
+            deck_being_renamed: None,
+            pending_delete: None,
             display_decks: true,
             card_currently_learning: None,
+            card_being_edited: None,
+            return_screen: CurrentScreen::ViewingDeck,
             face_showing: None,
+            tag_filter: BTreeSet::new(),
+            tag_filter_index: 0,
+            search_input: TextInput::single_line(),
+            search_results: Vec::new(),
+            search_index: 0,
+            query_input: TextInput::single_line(),
+            query_matches: Vec::new(),
+            query_error: None,
+            custom_study: None,
+            custom_study_return: CurrentScreen::Main,
+            browser: None,
+            external_edit: None,
+            card_shown_at: None,
+            answer_input: TextInput::single_line(),
+            multiple_choice: None,
+            review_log: Vec::new(),
+            history: History::default(),
+            keymap: Keymap::default(),
+            themes: theme::presets(),
+            theme_index: 0,
+            theme_before_picking: 0,
+            color_support: ColorSupport::detect(),
+            config_dir: None,
+            path_input: TextInput::single_line(),
+            csv_import: None,
+            import_error: None,
+            status_message: None,
+            export_all_decks: true,
+            statistics_deck: None,
+            collection_path: None,
+        }
+    }
+
+    /// creates the app with the decks stored at `path`, which is also where they will be saved to
+    pub fn load(path: PathBuf) -> io::Result<App> {
+        let collection = storage::load(&path)?;
+        let mut app = App::new();
+        app.decks = collection.decks;
+        app.review_log = collection.review_log;
+        app.collection_path = Some(path);
+        app.add_parent_decks();
+        Ok(app)
+    }
+
+    /// writes the decks to disk, called after every change to the collection and on exit
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(path) = &self.collection_path {
+            storage::save(path, &self.decks, &self.review_log)?;
+        }
+        Ok(())
+    }
+
+    /// every deck as it is now, taken before a change that adds, removes or reorders decks so it can be undone
+    fn snapshot(&self) -> Snapshot {
+        Snapshot::all(&self.decks, &self.review_log)
+    }
+
+    /// the decks at `indices` as they are now, taken before a change that only touches them
+    fn snapshot_decks(&self, indices: impl IntoIterator<Item = usize>) -> Snapshot {
+        Snapshot::of(&self.decks, indices, &self.review_log)
+    }
+
+    /// the indices of the decks holding the cards with `ids`
+    fn decks_with_cards(&self, ids: &BTreeSet<u64>) -> Vec<usize> {
+        (0..self.decks.len()).filter(|index| self.decks[*index].cards.iter().any(|card| ids.contains(&card.id))).collect()
+    }
+
+    /// records the change made since `before` was taken, `description` says what it did in the footer
+    fn record_change(&mut self, description: String, before: Snapshot) {
+        self.history.record(description, before, &self.decks, &self.review_log);
+    }
+
+    /// the key bindings of the current screen, `None` on the screens where keys are typed in as text or answer a popup
+    pub fn key_context(&self) -> Option<KeyContext> {
+        match self.current_screen {
+            CurrentScreen::Main => Some(KeyContext::DeckList),
+            CurrentScreen::ViewingDeck => Some(KeyContext::Deck),
+            CurrentScreen::LearningMode if !self.typing_answer() => Some(KeyContext::Learning),
+            CurrentScreen::Browsing if !self.editing_browser_query() && self.browser_action().is_none() => Some(KeyContext::Browser),
+            CurrentScreen::FilteringTags => Some(KeyContext::TagFilter),
+            CurrentScreen::Statistics => Some(KeyContext::Statistics),
+            CurrentScreen::PickingTheme => Some(KeyContext::ThemePicker),
+            _ => None,
         }
     }
 
+    /// takes back the last change to the collection. Taking back a grade in learning mode shows the card again.
+    pub fn undo(&mut self) {
+        let reviews = self.review_log.len();
+        let last_graded = self.review_log.last().map(|entry| entry.card_id);
+        match self.history.undo(&mut self.decks, &mut self.review_log) {
+            Some(description) => {
+                let ungraded = last_graded.filter(|_| self.review_log.len() < reviews);
+                self.collection_restored();
+                if let Some(card_id) = ungraded.filter(|_| self.current_screen == CurrentScreen::LearningMode) {
+                    if let Some(ids) = &mut self.custom_study {
+                        ids.retain(|id| *id != card_id);
+                        ids.insert(0, card_id);
+                    }
+                    let position = self.card_position(card_id);
+                    self.show_card_to_learn(position);
+                }
+                self.status_message = Some(format!("undid {description}"));
+            }
+            None => self.status_message = Some(String::from("nothing to undo")),
+        }
+    }
+
+    /// makes the last undone change again
+    pub fn redo(&mut self) {
+        let reviews = self.review_log.len();
+        match self.history.redo(&mut self.decks, &mut self.review_log) {
+            Some(description) => {
+                if self.review_log.len() > reviews {
+                    if let Some(entry) = self.review_log.last().cloned() {
+                        self.requeue_custom_study(entry.card_id, entry.grade);
+                    }
+                }
+                self.collection_restored();
+                self.status_message = Some(format!("redid {description}"));
+            }
+            None => self.status_message = Some(String::from("nothing to redo")),
+        }
+    }
+
+    /// keeps the selection and the card being learned pointing at cards that exist after an undo or redo
+    fn collection_restored(&mut self) {
+        self.selected_index = clamp_index(self.selected_index, self.decks.len());
+        self.selected_card_index = match self.selected_index.and_then(|index| self.decks.get(index)) {
+            Some(deck) => clamp_index(self.selected_card_index, deck.cards.len()),
+            None => None,
+        };
+        if self.selected_index.is_none() && matches!(self.current_screen, CurrentScreen::ViewingDeck | CurrentScreen::LearningMode) {
+            self.display_decks = true;
+            self.current_screen = CurrentScreen::Main;
+        }
+        if self.current_screen == CurrentScreen::LearningMode {
+            self.next_card_to_learn();
+        }
+        self.refresh_browser();
+    }
+
+    /// adds an empty deck, and the decks it is nested in when they don't exist yet, so the new deck is always the last one
     pub fn add_deck(&mut self, name: String) {
-        self.decks.push(Deck { name: name, cards: Vec::new(), date_last_learned: Utc::now() });
+        for parent in deck_tree::ancestor_names(&name) {
+            if self.decks.iter().all(|deck| deck.name != parent) {
+                self.push_deck(parent.to_string());
+            }
+        }
+        self.push_deck(name);
+    }
+
+    fn push_deck(&mut self, name: String) {
+        self.decks.push(Deck {
+            id: new_id(),
+            name,
+            cards: Vec::new(),
+            date_last_learned: Utc::now(),
+            scheduler: SchedulerKind::default(),
+            review_mode: ReviewMode::default(),
+            answer_options: AnswerOptions::default(),
+            collapsed: false,
+        });
+    }
+
+    /// adds the parents missing for decks named `Parent::Child`, like the ones of older collections or imports
+    fn add_parent_decks(&mut self) {
+        let names: Vec<String> = self.decks.iter().map(|deck| deck.name.clone()).collect();
+        for name in names {
+            for parent in deck_tree::ancestor_names(&name) {
+                if self.decks.iter().all(|deck| deck.name != parent) {
+                    self.push_deck(parent.to_string());
+                }
+            }
+        }
     }
 
     pub fn add_card(&mut self) {
         if let Some(index) = self.selected_index {
-            self.decks[index].cards.push(Card { front: self.front_input.clone(), back: self.back_input.clone(), last_guess: Guess::None });
+            self.decks[index].add_note(
+                self.front_input.text().to_string(),
+                self.back_input.text().to_string(),
+                parse_tags(self.tags_input.text()),
+                self.card_direction,
+            );
+        }
+    }
+
+    /// opens the deck name popup to add a new deck
+    pub fn start_adding_deck(&mut self) {
+        self.name_input.clear();
+        self.deck_being_renamed = None;
+        self.return_screen = self.current_screen;
+        self.adding_deck = true;
+        self.current_screen = CurrentScreen::AddingDeck;
+    }
+
+    /// opens the deck name popup pre-filled with the name of the selected deck
+    pub fn start_renaming_deck(&mut self) {
+        if let Some(deck) = self.selected_index.and_then(|index| self.decks.get(index)) {
+            self.name_input.set_text(&deck.name);
+            self.deck_being_renamed = self.selected_index;
+            self.return_screen = self.current_screen;
+            self.adding_deck = true;
+            self.current_screen = CurrentScreen::AddingDeck;
+        }
+    }
+
+    /// adds the deck or renames it, returning `false` when a deck of that name already exists.
+    /// Renaming a deck renames the decks nested in it, so none of their new names can be taken either.
+    pub fn save_deck_name(&mut self) -> bool {
+        let new_name = self.name_input.text().to_string();
+        let renamed = self.deck_being_renamed.and_then(|index| self.decks.get(index)).map(|deck| deck.name.clone());
+        // the names the deck and the decks nested in it end up with
+        let new_names: Vec<String> = match &renamed {
+            Some(old_name) => self
+                .decks
+                .iter()
+                .filter(|deck| deck.name == *old_name || deck_tree::is_descendant(&deck.name, old_name))
+                .map(|deck| format!("{new_name}{}", &deck.name[old_name.len()..]))
+                .collect(),
+            None => vec![new_name.clone()],
+        };
+        let is_renamed = |name: &str| {
+            renamed.as_ref().is_some_and(|old_name| name == old_name || deck_tree::is_descendant(name, old_name))
+        };
+        if let Some(taken) = new_names.iter().find(|name| self.decks.iter().any(|deck| deck.name == **name && !is_renamed(&deck.name))) {
+            self.status_message = Some(format!("There already is a deck called {taken}"));
+            return false;
+        }
+
+        let before = self.snapshot();
+        match renamed {
+            Some(old_name) => {
+                for deck in self.decks.iter_mut() {
+                    if deck.name == old_name {
+                        deck.name = new_name.clone();
+                    } else if deck_tree::is_descendant(&deck.name, &old_name) {
+                        deck.name = format!("{}{}", new_name, &deck.name[old_name.len()..]);
+                    }
+                }
+                self.add_parent_decks();
+                self.record_change(format!("rename deck {old_name}"), before);
+            }
+            None => {
+                self.add_deck(new_name.clone());
+                self.record_change(format!("add deck {new_name}"), before);
+            }
+        }
+        true
+    }
+
+    /// closes the deck name popup and goes back to where it was opened from
+    pub fn stop_editing_deck_name(&mut self) {
+        self.name_input.clear();
+        self.deck_being_renamed = None;
+        self.adding_deck = false;
+        self.current_screen = self.return_screen;
+    }
+
+    pub fn select_previous_deck(&mut self) {
+        let rows = deck_tree::tree_rows(&self.decks);
+        if let Some(position) = rows.iter().position(|row| Some(row.index) == self.selected_index) {
+            self.selected_index = Some(rows[position.saturating_sub(1)].index);
+        }
+    }
+
+    /// moves the selection down the deck tree, selecting the first deck when nothing is selected yet
+    pub fn select_next_deck(&mut self) {
+        let rows = deck_tree::tree_rows(&self.decks);
+        let position = rows.iter().position(|row| Some(row.index) == self.selected_index);
+        self.selected_index = next_index(position, rows.len()).map(|position| rows[position].index);
+    }
+
+    /// hides or shows the decks nested in the selected deck
+    pub fn toggle_collapsed(&mut self) {
+        if let Some(deck) = self.selected_index.and_then(|index| self.decks.get_mut(index)) {
+            deck.collapsed = !deck.collapsed;
+        }
+    }
+
+    /// the indices of the cards of the selected deck that pass the tag filter
+    pub fn visible_cards(&self) -> Vec<usize> {
+        let Some(deck) = self.selected_index.and_then(|index| self.decks.get(index)) else {
+            return Vec::new();
+        };
+        (0..deck.cards.len()).filter(|index| deck.cards[*index].has_any_tag(&self.tag_filter)).collect()
+    }
+
+    pub fn select_previous_card(&mut self) {
+        if let Some(index) = self.selected_card_index {
+            let previous = self.visible_cards().into_iter().rev().find(|visible| *visible < index);
+            self.selected_card_index = previous.or(Some(index));
+        }
+    }
+
+    pub fn select_next_card(&mut self) {
+        let visible = self.visible_cards();
+        self.selected_card_index = match self.selected_card_index {
+            Some(index) => visible.into_iter().find(|visible| *visible > index).or(Some(index)),
+            None => visible.first().copied(),
+        };
+    }
+
+    /// opens the search overlay to find a card in any deck
+    pub fn start_search(&mut self) {
+        self.search_input.clear();
+        self.search_results = Vec::new();
+        self.search_index = 0;
+        self.return_screen = self.current_screen;
+        self.current_screen = CurrentScreen::Searching;
+    }
+
+    /// searches again after the query changed
+    pub fn update_search(&mut self) {
+        self.search_results = search::search(&self.decks, self.search_input.text());
+        self.search_index = 0;
+    }
+
+    pub fn select_previous_search_result(&mut self) {
+        self.search_index = self.search_index.saturating_sub(1);
+    }
+
+    pub fn select_next_search_result(&mut self) {
+        self.search_index = next_index(Some(self.search_index), self.search_results.len()).unwrap_or_default();
+    }
+
+    /// shows the deck of the selected result with the card selected, making sure the card is not hidden by a filter
+    pub fn open_search_result(&mut self) {
+        let Some(result) = self.search_results.get(self.search_index).copied() else {
+            return;
+        };
+        let Some(deck) = self.decks.get(result.deck_index) else {
+            return;
+        };
+        let name = deck.name.clone();
+        for deck in self.decks.iter_mut() {
+            if deck_tree::is_descendant(&name, &deck.name) {
+                deck.collapsed = false;
+            }
+        }
+        self.tag_filter.clear();
+        self.selected_index = Some(result.deck_index);
+        self.selected_card_index = Some(result.card_index);
+        self.display_decks = false;
+        self.current_screen = CurrentScreen::ViewingDeck;
+    }
+
+    pub fn cancel_search(&mut self) {
+        self.current_screen = self.return_screen;
+    }
+
+    /// opens the custom study popup, starting from the deck being viewed
+    pub fn start_custom_study_query(&mut self) {
+        let query = match (self.current_screen, self.selected_index) {
+            (CurrentScreen::ViewingDeck, Some(index)) => format!("deck:\"{}\" ", self.decks[index].name),
+            _ => String::new(),
+        };
+        self.query_input.set_text(&query);
+        self.return_screen = self.current_screen;
+        self.current_screen = CurrentScreen::EnteringQuery;
+        self.update_query();
+    }
+
+    /// parses the query again after it changed and finds the cards matching it
+    pub fn update_query(&mut self) {
+        match Query::parse(self.query_input.text()) {
+            Ok(query) => {
+                self.query_matches = query::find_cards(&self.decks, &self.review_log, &query, Utc::now());
+                self.query_error = None;
+            }
+            Err(err) => {
+                self.query_matches.clear();
+                self.query_error = Some(err);
+            }
+        }
+    }
+
+    /// learns every card matching the query once, whether it is due or not.
+    /// Cards answered incorrectly come back at the end of the session.
+    pub fn start_custom_study(&mut self) {
+        if self.query_error.is_some() || self.query_matches.is_empty() {
+            return;
         }
+        let ids = self.query_matches.iter().map(|(deck_index, card_index)| self.decks[*deck_index].cards[*card_index].id).collect();
+        self.custom_study = Some(ids);
+        // the editor can be opened while learning, so the screen to go back to is kept apart from `return_screen`
+        self.custom_study_return = self.return_screen;
+        self.display_decks = false;
+        self.selected_card_index = None;
+        self.current_screen = CurrentScreen::LearningMode;
+        self.next_card_to_learn();
     }
 
+    pub fn cancel_custom_study_query(&mut self) {
+        self.current_screen = self.return_screen;
+    }
+
+    /// leaves learning mode for the deck being studied, ending a custom study session
+    pub fn stop_learning(&mut self) {
+        self.current_screen = match self.custom_study.take() {
+            Some(_) => self.custom_study_return,
+            None => CurrentScreen::ViewingDeck,
+        };
+        self.display_decks = self.current_screen == CurrentScreen::Main;
+        self.selected_card_index = None;
+    }
+
+    /// opens the card browser on the cards of the deck being viewed, or on every card from the deck list
+    pub fn open_browser(&mut self) {
+        let query = match (self.current_screen, self.selected_index) {
+            (CurrentScreen::ViewingDeck, Some(index)) => format!("deck:\"{}\"", self.decks[index].name),
+            _ => String::new(),
+        };
+        self.browser = Some(Browser::new(query));
+        self.refresh_browser();
+        self.current_screen = CurrentScreen::Browsing;
+    }
+
+    /// goes back to the deck list or the deck the browser was opened from
+    pub fn close_browser(&mut self) {
+        self.browser = None;
+        self.current_screen = if self.display_decks { CurrentScreen::Main } else { CurrentScreen::ViewingDeck };
+    }
+
+    /// lists the cards matching the browser query again, after the query or the cards changed
+    pub fn refresh_browser(&mut self) {
+        if let Some(browser) = &mut self.browser {
+            browser.refresh(&self.decks, &self.review_log, Utc::now());
+        }
+    }
+
+    pub fn editing_browser_query(&self) -> bool {
+        self.browser.as_ref().is_some_and(|browser| browser.editing_query)
+    }
+
+    pub fn browser_action(&self) -> Option<BulkAction> {
+        self.browser.as_ref().and_then(|browser| browser.action)
+    }
+
+    /// the ids of the cards the next bulk action changes
+    pub fn browser_targets(&self) -> BTreeSet<u64> {
+        self.browser.as_ref().map(|browser| browser.targets(&self.decks)).unwrap_or_default()
+    }
+
+    pub fn select_previous_browser_row(&mut self) {
+        if let Some(browser) = &mut self.browser {
+            browser.select_previous();
+        }
+    }
+
+    pub fn select_next_browser_row(&mut self) {
+        if let Some(browser) = &mut self.browser {
+            browser.select_next();
+        }
+    }
+
+    pub fn toggle_browser_mark(&mut self) {
+        if let Some(browser) = &mut self.browser {
+            browser.toggle_mark(&self.decks);
+        }
+    }
+
+    pub fn toggle_browser_mark_all(&mut self) {
+        if let Some(browser) = &mut self.browser {
+            browser.toggle_mark_all();
+        }
+    }
+
+    /// sorts the browser by the column with the 1-based `number`
+    pub fn sort_browser(&mut self, number: usize) {
+        if let (Some(browser), Some(column)) = (&mut self.browser, number.checked_sub(1).and_then(|index| BrowserColumn::ALL.get(index))) {
+            browser.sort_by(*column, &self.decks);
+        }
+    }
+
+    /// opens the card under the cursor of the browser in the card editor
+    pub fn edit_browser_card(&mut self) {
+        if let Some((deck_index, card_index)) = self.browser.as_ref().and_then(Browser::selected_row) {
+            self.start_editing_card(deck_index, card_index);
+        }
+    }
+
+    /// starts typing in the text a bulk action needs, like the deck to move the cards to
+    pub fn start_browser_action(&mut self, action: BulkAction) {
+        if self.browser_targets().is_empty() {
+            return;
+        }
+        if let Some(browser) = &mut self.browser {
+            browser.action = Some(action);
+            browser.action_input.clear();
+        }
+    }
+
+    pub fn cancel_browser_action(&mut self) {
+        if let Some(browser) = &mut self.browser {
+            browser.action = None;
+        }
+    }
+
+    pub fn apply_browser_action(&mut self) {
+        let Some((action, input)) = self.browser.as_mut().and_then(|browser| Some((browser.action.take()?, browser.action_input.text().trim().to_string()))) else {
+            return;
+        };
+        match action {
+            BulkAction::Move if !input.is_empty() => self.move_browser_cards(input),
+            BulkAction::Move => {}
+            BulkAction::Tag => self.tag_browser_cards(&input),
+        }
+    }
+
+    /// moves the targeted cards to the end of the deck called `name`, creating it when it doesn't exist
+    fn move_browser_cards(&mut self, name: String) {
+        let targets = self.browser_targets();
+        let existing = self.decks.iter().position(|deck| deck.name == name);
+        // creating the deck changes the list of decks, which only a snapshot of every deck can take back
+        let before = match existing {
+            Some(index) => self.snapshot_decks(self.decks_with_cards(&targets).into_iter().chain([index])),
+            None => self.snapshot(),
+        };
+        let deck_index = match existing {
+            Some(index) => index,
+            None => {
+                self.add_deck(name.clone());
+                self.decks.len() - 1
+            }
+        };
+        let mut moved = Vec::new();
+        for (index, deck) in self.decks.iter_mut().enumerate() {
+            if index != deck_index {
+                let (leaving, staying) = deck.cards.drain(..).partition(|card| targets.contains(&card.id));
+                deck.cards = staying;
+                moved.extend::<Vec<Card>>(leaving);
+            }
+        }
+        self.status_message = Some(format!("moved {} cards to {name}", moved.len()));
+        let description = format!("move {} cards to {name}", moved.len());
+        self.decks[deck_index].cards.extend(moved);
+        self.browser_cards_changed();
+        self.record_change(description, before);
+    }
+
+    /// adds the tags in `input` to the targeted cards, tags written as `-tag` are removed instead
+    fn tag_browser_cards(&mut self, input: &str) {
+        let targets = self.browser_targets();
+        let before = self.snapshot_decks(self.decks_with_cards(&targets));
+        let tags = parse_tags(input);
+        for card in self.decks.iter_mut().flat_map(|deck| deck.cards.iter_mut()).filter(|card| targets.contains(&card.id)) {
+            for tag in &tags {
+                match tag.strip_prefix('-') {
+                    Some(removed) => card.tags.remove(removed),
+                    None => card.tags.insert(tag.clone()),
+                };
+            }
+        }
+        self.status_message = Some(format!("tagged {} cards", targets.len()));
+        self.browser_cards_changed();
+        self.record_change(format!("tag {} cards", targets.len()), before);
+    }
+
+    /// makes the targeted cards new again, forgetting everything the scheduler knew about them
+    pub fn reset_browser_cards(&mut self) {
+        let targets = self.browser_targets();
+        let before = self.snapshot_decks(self.decks_with_cards(&targets));
+        let now = Utc::now();
+        for card in self.decks.iter_mut().flat_map(|deck| deck.cards.iter_mut()).filter(|card| targets.contains(&card.id)) {
+            card.review = ReviewState::new(now);
+            card.last_guess = Guess::None;
+        }
+        self.status_message = Some(format!("reset the scheduling of {} cards", targets.len()));
+        self.browser_cards_changed();
+        self.record_change(format!("reset {} cards", targets.len()), before);
+    }
+
+    /// suspends the targeted cards, or unsuspends them when they all are suspended already
+    pub fn toggle_browser_cards_suspended(&mut self) {
+        let targets = self.browser_targets();
+        let before = self.snapshot_decks(self.decks_with_cards(&targets));
+        let mut cards: Vec<&mut Card> =
+            self.decks.iter_mut().flat_map(|deck| deck.cards.iter_mut()).filter(|card| targets.contains(&card.id)).collect();
+        let suspend = !cards.iter().all(|card| card.suspended);
+        for card in cards.iter_mut() {
+            card.suspended = suspend;
+        }
+        let verb = if suspend { "suspend" } else { "unsuspend" };
+        let count = cards.len();
+        self.status_message = Some(format!("{verb}ed {count} cards"));
+        self.browser_cards_changed();
+        self.record_change(format!("{verb} {count} cards"), before);
+    }
+
+    /// keeps the browser and the selection of the viewed deck in bounds after cards were moved or deleted
+    fn browser_cards_changed(&mut self) {
+        if let Some(deck) = self.selected_index.and_then(|index| self.decks.get(index)) {
+            self.selected_card_index = clamp_index(self.selected_card_index, deck.cards.len());
+        }
+        self.refresh_browser();
+    }
+
+    /// opens the popup listing the tags of the selected deck to filter its cards by
+    pub fn open_tag_filter(&mut self) {
+        self.tag_filter_index = 0;
+        self.return_screen = self.current_screen;
+        self.current_screen = CurrentScreen::FilteringTags;
+    }
+
+    pub fn close_tag_filter(&mut self) {
+        self.current_screen = self.return_screen;
+    }
+
+    /// the tags of the selected deck in the order they are listed in the tag filter popup
+    pub fn deck_tags(&self) -> Vec<(String, usize)> {
+        self.selected_index
+            .and_then(|index| self.decks.get(index))
+            .map(|deck| deck.tag_counts().into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn select_previous_tag(&mut self) {
+        self.tag_filter_index = self.tag_filter_index.saturating_sub(1);
+    }
+
+    pub fn select_next_tag(&mut self) {
+        self.tag_filter_index = next_index(Some(self.tag_filter_index), self.deck_tags().len()).unwrap_or_default();
+    }
+
+    /// adds the selected tag to the filter or removes it again
+    pub fn toggle_tag_filter(&mut self) {
+        if let Some((tag, _)) = self.deck_tags().into_iter().nth(self.tag_filter_index) {
+            if !self.tag_filter.remove(&tag) {
+                self.tag_filter.insert(tag);
+            }
+            self.selected_card_index = None;
+        }
+    }
+
+    pub fn clear_tag_filter(&mut self) {
+        self.tag_filter.clear();
+        self.selected_card_index = None;
+    }
+
+    /// asks for confirmation before `target` is deleted
+    pub fn request_delete(&mut self, target: DeleteTarget) {
+        self.pending_delete = Some(target);
+        self.return_screen = self.current_screen;
+        self.current_screen = CurrentScreen::ConfirmingDelete;
+    }
+
+    /// deletes the deck, with the decks nested in it, or the card the user confirmed and keeps the selection within bounds
+    pub fn confirm_delete(&mut self) {
+        match self.pending_delete.take() {
+            Some(DeleteTarget::Deck(index)) if index < self.decks.len() => {
+                let before = self.snapshot();
+                let name = self.decks[index].name.clone();
+                let position = deck_tree::tree_rows(&self.decks).iter().position(|row| row.index == index).unwrap_or_default();
+                let mut family = deck_tree::family(&self.decks, index);
+                family.sort_unstable();
+                for index in family.into_iter().rev() {
+                    self.decks.remove(index);
+                }
+                let rows = deck_tree::tree_rows(&self.decks);
+                self.selected_index = clamp_index(Some(position), rows.len()).map(|position| rows[position].index);
+                self.selected_card_index = None;
+                self.record_change(format!("delete deck {name}"), before);
+            }
+            Some(DeleteTarget::Card(card_index)) => {
+                let before = self.snapshot_decks(self.selected_index);
+                if let Some(deck) = self.selected_index.and_then(|index| self.decks.get_mut(index)) {
+                    if card_index < deck.cards.len() {
+                        let card = deck.cards.remove(card_index);
+                        self.selected_card_index = clamp_index(Some(card_index), deck.cards.len());
+                        self.record_change(format!("delete card {}", short_text(&card.front)), before);
+                    }
+                }
+            }
+            Some(DeleteTarget::BrowserCards) => {
+                let targets = self.browser_targets();
+                let before = self.snapshot_decks(self.decks_with_cards(&targets));
+                for deck in self.decks.iter_mut() {
+                    deck.cards.retain(|card| !targets.contains(&card.id));
+                }
+                self.status_message = Some(format!("deleted {} cards", targets.len()));
+                self.browser_cards_changed();
+                self.record_change(format!("delete {} cards", targets.len()), before);
+            }
+            _ => {}
+        }
+        self.current_screen = self.return_screen;
+        // there is no deck left to view once the viewed one is gone
+        if self.current_screen == CurrentScreen::ViewingDeck && self.selected_index.is_none() {
+            self.display_decks = true;
+            self.current_screen = CurrentScreen::Main;
+        }
+    }
+
+    pub fn cancel_delete(&mut self) {
+        self.pending_delete = None;
+        self.current_screen = self.return_screen;
+    }
+
+    /// inserts a copy of the selected deck after it, the copied cards start out unlearned
+    pub fn duplicate_deck(&mut self) {
+        if let Some(index) = self.selected_index.filter(|index| *index < self.decks.len()) {
+            let before = self.snapshot();
+            let mut copy = self.decks[index].clone();
+            copy.id = new_id();
+            copy.name = format!("{} (copy)", copy.name);
+            // the copied notes get their own ids so editing them doesn't touch the original cards
+            let mut note_ids = HashMap::new();
+            for card in copy.cards.iter_mut() {
+                let mut unlearned = Card::new(card.front.clone(), card.back.clone());
+                unlearned.kind = card.kind;
+                unlearned.tags = card.tags.clone();
+                unlearned.note_id = card.note_id.map(|note_id| *note_ids.entry(note_id).or_insert_with(new_id));
+                *card = unlearned;
+            }
+            let description = format!("duplicate deck {}", self.decks[index].name);
+            self.decks.insert(index + 1, copy);
+            self.selected_index = Some(index + 1);
+            self.record_change(description, before);
+        }
+    }
+
+    /// inserts an unlearned copy of the selected card after it
+    pub fn duplicate_card(&mut self) {
+        let (Some(deck_index), Some(card_index)) = (self.selected_index, self.selected_card_index) else {
+            return;
+        };
+        let before = self.snapshot_decks([deck_index]);
+        if let Some(deck) = self.decks.get_mut(deck_index) {
+            if let Some(card) = deck.cards.get(card_index) {
+                let mut copy = Card::new(card.front.clone(), card.back.clone());
+                copy.kind = card.kind;
+                copy.tags = card.tags.clone();
+                let description = format!("duplicate card {}", short_text(&card.front));
+                deck.cards.insert(card_index + 1, copy);
+                self.selected_card_index = Some(card_index + 1);
+                self.record_change(description, before);
+            }
+        }
+    }
+
+    /// moves the selected deck one place up (`-1`) or down (`1`) in the deck tree
+    pub fn move_deck(&mut self, offset: isize) {
+        let Some(deck) = self.selected_index.and_then(|index| self.decks.get(index)) else {
+            return;
+        };
+        // decks only move between the other decks nested in the same parent
+        let parent = deck_tree::parent_name(&deck.name);
+        let siblings: Vec<usize> =
+            (0..self.decks.len()).filter(|index| deck_tree::parent_name(&self.decks[*index].name) == parent).collect();
+        let Some(position) = siblings.iter().position(|index| Some(*index) == self.selected_index) else {
+            return;
+        };
+        if let Some(new_position) = moved_index(position, offset, siblings.len()) {
+            let before = self.snapshot();
+            let description = format!("move deck {}", deck.name);
+            self.decks.swap(siblings[position], siblings[new_position]);
+            self.selected_index = Some(siblings[new_position]);
+            self.record_change(description, before);
+        }
+    }
+
+    /// moves the selected card one place up (`-1`) or down (`1`) in its deck
+    pub fn move_card(&mut self, offset: isize) {
+        let (Some(deck_index), Some(card_index)) = (self.selected_index, self.selected_card_index) else {
+            return;
+        };
+        let before = self.snapshot_decks([deck_index]);
+        if let Some(deck) = self.decks.get_mut(deck_index) {
+            if let Some(new_index) = moved_index(card_index, offset, deck.cards.len()) {
+                let description = format!("move card {}", short_text(&deck.cards[card_index].front));
+                deck.cards.swap(card_index, new_index);
+                self.selected_card_index = Some(new_index);
+                self.record_change(description, before);
+            }
+        }
+    }
+
+    /// opens the card editor for a new card of the selected deck
+    pub fn start_adding_card(&mut self) {
+        self.front_input.clear();
+        self.back_input.clear();
+        // new cards get the tags the deck is filtered by, so they show up in the filtered list
+        self.tags_input.set_text(&self.tag_filter.iter().cloned().collect::<Vec<_>>().join(" "));
+        self.card_direction = CardDirection::default();
+        self.card_being_edited = None;
+        self.return_screen = self.current_screen;
+        self.card_currently_editing = Some(EditorField::Front);
+        self.current_screen = CurrentScreen::EditingCard;
+    }
+
+    /// opens the card editor pre-filled with an existing card, which is in a nested deck when editing while learning a parent deck
+    pub fn start_editing_card(&mut self, deck_index: usize, card_index: usize) {
+        let Some(deck) = self.decks.get(deck_index) else {
+            return;
+        };
+        let Some(card) = deck.cards.get(card_index) else {
+            return;
+        };
+        self.front_input.set_text(&card.front);
+        self.back_input.set_text(&card.back);
+        self.tags_input.set_text(&card.tags.iter().cloned().collect::<Vec<_>>().join(" "));
+        self.card_direction = deck.note_direction(card_index);
+        self.card_being_edited = Some((deck_index, card_index));
+        self.return_screen = self.current_screen;
+        self.card_currently_editing = Some(EditorField::Front);
+        self.current_screen = CurrentScreen::EditingCard;
+    }
+
+    /// opens the card in `$EDITOR` without going through the card editor popup
+    pub fn start_external_edit(&mut self, deck_index: usize, card_index: usize) {
+        self.start_editing_card(deck_index, card_index);
+        if self.current_screen == CurrentScreen::EditingCard {
+            self.external_edit = Some(ExternalEdit::SaveCard);
+        }
+    }
+
+    /// the fields of the card editor in the format of the file opened in `$EDITOR`
+    pub fn external_editor_text(&self) -> String {
+        external_editor::note_to_text(&EditedNote {
+            front: self.front_input.text().to_string(),
+            back: self.back_input.text().to_string(),
+            tags: self.tags_input.text().to_string(),
+        })
+    }
+
+    /// takes the card back from `$EDITOR`, the outcome is shown in the footer
+    pub fn finish_external_edit(&mut self, edited: io::Result<String>) {
+        let Some(external_edit) = self.external_edit.take() else {
+            return;
+        };
+        let unchanged = edited.as_ref().is_ok_and(|text| *text == self.external_editor_text());
+        let note = edited.map_err(|err| format!("could not run the editor: {err}")).and_then(|text| external_editor::parse_note(&text));
+        match note {
+            Ok(Some(note)) if !unchanged => {
+                self.front_input.set_text(&note.front);
+                self.back_input.set_text(&note.back);
+                self.tags_input.set_text(&note.tags);
+                if external_edit == ExternalEdit::SaveCard {
+                    self.save_edited_card();
+                    self.stop_editing_card();
+                }
+                self.status_message = Some(String::from("card updated from the editor"));
+                return;
+            }
+            Ok(Some(_)) => self.status_message = Some(String::from("the card was not changed")),
+            Ok(None) => self.status_message = Some(String::from("edit cancelled")),
+            Err(err) => self.status_message = Some(err),
+        }
+        if external_edit == ExternalEdit::SaveCard {
+            self.stop_editing_card();
+        }
+    }
+
+    /// stores the contents of the editor, updating the edited card in place so its scheduling is kept
+    pub fn save_edited_card(&mut self) {
+        // an edited card stays in its deck and a new one goes to the selected deck
+        let before = self.snapshot_decks(self.card_being_edited.map(|(deck_index, _)| deck_index).or(self.selected_index));
+        let front = short_text(self.front_input.text());
+        match self.card_being_edited {
+            Some((deck_index, card_index)) => {
+                let Some(deck) = self.decks.get_mut(deck_index) else {
+                    return;
+                };
+                let edited_index = deck.update_note(
+                    card_index,
+                    self.front_input.text().to_string(),
+                    self.back_input.text().to_string(),
+                    parse_tags(self.tags_input.text()),
+                    self.card_direction,
+                );
+                let card_count = deck.cards.len();
+                // editing a cloze or bidirectional note can add or remove cards, so the indices pointing into the deck move along
+                if self.return_screen == CurrentScreen::LearningMode {
+                    self.card_currently_learning = edited_index.map(|card_index| (deck_index, card_index));
+                    // the choices were made from the old answer, so the card is asked again with new ones
+                    if self.learning_review_mode() == ReviewMode::MultipleChoice {
+                        self.show_card_to_learn(self.card_currently_learning);
+                    }
+                } else if self.selected_index == Some(deck_index) {
+                    self.selected_card_index = clamp_index(edited_index.or(self.selected_card_index), card_count);
+                }
+                self.record_change(format!("edit card {front}"), before);
+            }
+            None => {
+                self.add_card();
+                self.record_change(format!("add card {front}"), before);
+            }
+        }
+    }
+
+    /// closes the card editor and goes back to where it was opened from
+    pub fn stop_editing_card(&mut self) {
+        self.front_input.clear();
+        self.back_input.clear();
+        self.tags_input.clear();
+        self.card_direction = CardDirection::default();
+        self.card_being_edited = None;
+        self.card_currently_editing = None;
+        self.current_screen = self.return_screen;
+        self.refresh_browser();
+    }
+
+    /// picks the most overdue card with one of the filtered tags from the selected deck and the decks nested in it,
+    /// or `None` when nothing is due
     pub fn next_card_to_learn(&mut self) {
+        let now = Utc::now();
+        let next = match &self.custom_study {
+            // cards deleted or suspended during the session are skipped
+            Some(ids) => ids
+                .iter()
+                .filter_map(|id| self.card_position(*id))
+                .find(|(deck_index, card_index)| !self.decks[*deck_index].cards[*card_index].suspended),
+            None => deck_tree::family(&self.decks, self.selected_index.unwrap_or_default())
+                .into_iter()
+                .flat_map(|deck_index| self.decks[deck_index].cards.iter().enumerate().map(move |(card_index, card)| (deck_index, card_index, card)))
+                .filter(|(_, _, card)| card.is_due(now) && card.has_any_tag(&self.tag_filter))
+                .min_by_key(|(_, _, card)| card.review.due)
+                .map(|(deck_index, card_index, _)| (deck_index, card_index)),
+        };
+        self.show_card_to_learn(next);
+    }
+
+    /// shows the front of the card at `position` in learning mode
+    fn show_card_to_learn(&mut self, position: Option<(usize, usize)>) {
         self.face_showing = Some(CardFace::CardFront);
-    
-        let mut rng = rand::thread_rng();
-        let card_index = rng.gen_range(0..self.decks[self.selected_index.unwrap_or(0)].cards.len());
-        self.card_currently_learning = Some(card_index);
+        self.card_shown_at = Some(Instant::now());
+        self.answer_input.clear();
+        self.card_currently_learning = position;
+        // the studied deck decides how cards are answered, the distractors come from the deck of the card
+        self.multiple_choice = match self.card_currently_learning {
+            Some((deck_index, card_index)) if self.learning_review_mode() == ReviewMode::MultipleChoice => {
+                MultipleChoice::new(&self.decks[deck_index], card_index)
+            }
+            _ => None,
+        };
     }
 
-    pub fn toggle_card_currently_editing(&mut self) {
-        if let Some(edit_mode) = &self.card_currently_editing {
-            match edit_mode {
-                CardFace::CardFront => {
-                    self.card_currently_editing = Some(CardFace::CardBack)
+    /// the deck and card index of the card with `id`
+    fn card_position(&self, id: u64) -> Option<(usize, usize)> {
+        self.decks.iter().enumerate().find_map(|(deck_index, deck)| {
+            deck.cards.iter().position(|card| card.id == id).map(|card_index| (deck_index, card_index))
+        })
+    }
+
+    /// how the cards of the deck being studied are answered
+    fn learning_review_mode(&self) -> ReviewMode {
+        self.selected_index.and_then(|index| self.decks.get(index)).map(|deck| deck.review_mode).unwrap_or_default()
+    }
+
+    /// the card being learned, which can be in a deck nested in the selected one
+    pub fn learning_card(&self) -> Option<&Card> {
+        let (deck_index, card_index) = self.card_currently_learning?;
+        self.decks.get(deck_index)?.cards.get(card_index)
+    }
+
+    /// grades the card currently being learned with the scheduler of its deck and moves on to the next due card
+    pub fn answer_card(&mut self, guess: Guess) {
+        if let Some((deck_index, card_index)) = self.card_currently_learning {
+            let before = self.snapshot_decks([deck_index]);
+            let now = Utc::now();
+            let deck = &mut self.decks[deck_index];
+            let scheduler = deck.scheduler.scheduler();
+            let card = &mut deck.cards[card_index];
+            let previous_interval = card.review.interval;
+            card.review(guess, now, scheduler);
+            let time_spent_ms = self.card_shown_at.map(|shown_at| shown_at.elapsed().as_millis() as u64).unwrap_or_default();
+            self.review_log.push(ReviewLogEntry {
+                card_id: card.id,
+                deck_id: deck.id,
+                timestamp: now,
+                grade: guess,
+                previous_interval,
+                new_interval: card.review.interval,
+                time_spent_ms,
+            });
+            deck.date_last_learned = now;
+
+            let card_id = card.id;
+            let description = format!("grade of {}", short_text(&card.front));
+            self.requeue_custom_study(card_id, guess);
+            self.record_change(description, before);
+        }
+        self.next_card_to_learn();
+    }
+
+    /// takes a graded card out of the custom study session, cards answered incorrectly come back at its end
+    fn requeue_custom_study(&mut self, card_id: u64, guess: Guess) {
+        if let Some(ids) = &mut self.custom_study {
+            ids.retain(|id| *id != card_id);
+            if guess == Guess::Incorrect {
+                ids.push(card_id);
+            }
+        }
+    }
+
+    /// whether the back of the card being learned is showing, grades are only accepted after that
+    pub fn answer_revealed(&self) -> bool {
+        self.card_currently_learning.is_some() && matches!(self.face_showing, Some(CardFace::CardBack))
+    }
+
+    /// whether the answer to the card being learned is being typed in before it is revealed
+    pub fn typing_answer(&self) -> bool {
+        self.card_currently_learning.is_some()
+            && matches!(self.face_showing, Some(CardFace::CardFront))
+            && self.learning_review_mode() == ReviewMode::TypeAnswer
+    }
+
+    /// the typed answer compared with the card being learned, once it is revealed in the type the answer mode
+    pub fn answer_check(&self) -> Option<AnswerCheck> {
+        if !self.answer_revealed() {
+            return None;
+        }
+        let deck = self.decks.get(self.selected_index?)?;
+        if deck.review_mode != ReviewMode::TypeAnswer {
+            return None;
+        }
+        Some(check_answer(self.answer_input.text(), &self.learning_card()?.expected_answer(), deck.answer_options))
+    }
+
+    /// whether the answer to the card being learned is still to be picked among the multiple choices
+    pub fn choosing_answer(&self) -> bool {
+        self.card_currently_learning.is_some() && self.multiple_choice.as_ref().is_some_and(|choice| choice.picked.is_none())
+    }
+
+    /// picks the choice shown with `number`, counting from 1, and reveals the answer
+    pub fn pick_choice(&mut self, number: usize) {
+        if let Some(multiple_choice) = self.multiple_choice.as_mut() {
+            if number >= 1 && number <= multiple_choice.choices.len() && multiple_choice.picked.is_none() {
+                multiple_choice.picked = Some(number - 1);
+                self.face_showing = Some(CardFace::CardBack);
+            }
+        }
+    }
+
+    /// the grade the picked choice maps onto, once a choice was picked
+    pub fn choice_guess(&self) -> Option<Guess> {
+        self.card_currently_learning?;
+        self.multiple_choice.as_ref()?.guess()
+    }
+
+    /// changes how the selected deck compares typed answers
+    pub fn update_answer_options(&mut self, change: impl FnOnce(&mut AnswerOptions)) {
+        let before = self.snapshot_decks(self.selected_index);
+        if let Some(deck) = self.selected_index.and_then(|index| self.decks.get_mut(index)) {
+            change(&mut deck.answer_options);
+            self.record_change(String::from("change of the answer options"), before);
+        }
+    }
+
+    /// asks for the path of a CSV/TSV file to import, into the selected deck when coming from the deck view
+    pub fn start_import(&mut self) {
+        self.path_input.clear();
+        self.import_error = None;
+        self.return_screen = self.current_screen;
+        self.current_screen = CurrentScreen::EnteringImportPath;
+    }
+
+    /// reads the typed in file and shows the import preview.
+    /// Anki packages are imported straight away since their notes already say what goes on each side.
+    pub fn open_import(&mut self) {
+        let path = expand_home(self.path_input.text().trim());
+        if matches!(path.extension().and_then(|extension| extension.to_str()), Some("apkg" | "colpkg")) {
+            match anki_import::import_package(&path) {
+                Ok(import) => {
+                    self.add_anki_import(import);
+                    self.cancel_import();
+                    self.display_decks = true;
+                    self.current_screen = CurrentScreen::Main;
                 }
-                CardFace::CardBack => {
-                    self.card_currently_editing = Some(CardFace::CardFront)
+                Err(err) => self.import_error = Some(format!("could not import {}: {err}", path.display())),
+            }
+            return;
+        }
+        let target = if self.return_screen == CurrentScreen::ViewingDeck { self.selected_index } else { None };
+        match CsvImport::open(&path, target, &self.decks) {
+            Ok(import) => {
+                self.csv_import = Some(import);
+                self.import_error = None;
+                self.display_decks = false;
+                self.current_screen = CurrentScreen::ImportPreview;
+            }
+            Err(err) => self.import_error = Some(format!("could not open {}: {err}", path.display())),
+        }
+    }
+
+    /// changes the import options with `change` and updates the preview
+    pub fn update_import(&mut self, change: impl FnOnce(&mut CsvImport, &[Deck])) {
+        if let Some(import) = self.csv_import.as_mut() {
+            change(import, &self.decks);
+            import.refresh(&self.decks);
+        }
+    }
+
+    /// adds the cards of the import preview to their deck and shows that deck
+    pub fn finish_import(&mut self) {
+        // the preview stays open with its rows when the chosen deck is gone
+        if let Some(CsvImport { target: ImportTarget::ExistingDeck(index), .. }) = self.csv_import {
+            if index >= self.decks.len() {
+                self.status_message = Some(String::from("the deck to import into no longer exists, press n to pick another"));
+                return;
+            }
+        }
+        let Some(import) = self.csv_import.take() else {
+            return;
+        };
+        let before = self.snapshot();
+        let deck_index = match import.target {
+            ImportTarget::ExistingDeck(index) => index,
+            // a file named like an existing deck is imported into it, deck names stay unique
+            ImportTarget::NewDeck(name) => match self.decks.iter().position(|deck| deck.name == name) {
+                Some(index) => index,
+                None => {
+                    self.add_deck(name);
+                    self.decks.len() - 1
+                }
+            },
+        };
+        // cloze rows become a card per deletion, like notes written in the card editor
+        for (front, back, tags) in import.plan.cards {
+            self.decks[deck_index].add_note(front, back, tags, CardDirection::FrontToBack);
+        }
+        self.record_change(format!("import into {}", self.decks[deck_index].name), before);
+        self.selected_index = Some(deck_index);
+        self.selected_card_index = None;
+        self.display_decks = false;
+        self.current_screen = CurrentScreen::ViewingDeck;
+    }
+
+    /// adds the decks of an Anki package, merging them into decks with the same name.
+    /// Cards that were imported before are skipped so importing a package again only adds what is new.
+    pub fn add_anki_import(&mut self, import: AnkiImport) {
+        let before = self.snapshot();
+        let mut added_cards = BTreeSet::new();
+        let mut skipped = 0;
+        let deck_count = import.decks.len();
+        // cards imported before are skipped wherever they are now, even after their deck was renamed or they were moved
+        let existing_ids: HashSet<u64> = self.decks.iter().flat_map(|deck| deck.cards.iter().map(|card| card.id)).collect();
+
+        for mut deck in import.decks {
+            let (cards, already_there): (Vec<Card>, Vec<Card>) = std::mem::take(&mut deck.cards)
+                .into_iter()
+                .partition(|card| !existing_ids.contains(&card.id) && !added_cards.contains(&card.id));
+            skipped += already_there.len();
+            let index = match self.decks.iter().position(|existing| existing.name == deck.name) {
+                Some(index) => index,
+                None if cards.is_empty() => continue,
+                None => {
+                    self.decks.push(deck.clone());
+                    self.decks.len() - 1
                 }
             };
+            let existing = &mut self.decks[index];
+            added_cards.extend(cards.iter().map(|card| card.id));
+            existing.cards.extend(cards);
+            existing.date_last_learned = existing.date_last_learned.max(deck.date_last_learned);
+        }
+
+        // the review log refers to decks by id, which changes when cards are merged into an existing deck
+        let card_decks: HashMap<u64, u64> = self
+            .decks
+            .iter()
+            .flat_map(|deck| deck.cards.iter().map(move |card| (card.id, deck.id)))
+            .filter(|(card_id, _)| added_cards.contains(card_id))
+            .collect();
+        for mut entry in import.review_log {
+            if let Some(deck_id) = card_decks.get(&entry.card_id) {
+                entry.deck_id = *deck_id;
+                self.review_log.push(entry);
+            }
+        }
+        self.add_parent_decks();
+        self.record_change(format!("import of {deck_count} Anki decks"), before);
+
+        self.status_message = Some(if skipped > 0 {
+            format!("Imported {} cards from {} Anki decks, skipped {skipped} cards imported before", added_cards.len(), deck_count)
         } else {
-            self.card_currently_editing = Some(CardFace::CardFront);
+            format!("Imported {} cards from {} Anki decks", added_cards.len(), deck_count)
+        });
+    }
+
+    pub fn cancel_import(&mut self) {
+        self.csv_import = None;
+        self.import_error = None;
+        self.display_decks = self.return_screen == CurrentScreen::Main;
+        self.current_screen = self.return_screen;
+    }
+
+    /// the decks exported from the exit screen
+    pub fn decks_to_export(&self) -> Vec<&Deck> {
+        match self.selected_index {
+            Some(index) if !self.export_all_decks => self.deck_family(index),
+            _ => self.decks.iter().collect(),
         }
     }
 
-    pub fn print_json(&self) -> Result<()> {
-        let output = serde_json::to_string(&self.pairs)?;
-        println!("{}", output);
-        Ok(())
+    /// the deck at `index` and the decks nested in it
+    pub fn deck_family(&self, index: usize) -> Vec<&Deck> {
+        deck_tree::family(&self.decks, index).into_iter().map(|index| &self.decks[index]).collect()
+    }
+
+    /// opens the statistics screen for the selected deck, or for all decks when coming from the deck list
+    pub fn open_statistics(&mut self) {
+        self.statistics_deck = if self.display_decks { None } else { self.selected_index };
+        self.display_decks = false;
+        self.current_screen = CurrentScreen::Statistics;
+    }
+
+    /// returns to the screen the statistics were opened from
+    pub fn close_statistics(&mut self) {
+        if self.statistics_deck.is_some() {
+            self.current_screen = CurrentScreen::ViewingDeck;
+        } else {
+            self.display_decks = true;
+            self.current_screen = CurrentScreen::Main;
+        }
+    }
+
+    /// reads the themes from `config_dir` and switches to the one picked last time.
+    /// Theme files that can't be read are left out and the footer says why, they don't keep the app from starting.
+    pub fn load_themes(&mut self, config_dir: PathBuf) {
+        let (themes, mut problems) = theme::load_themes(&config_dir);
+        self.themes = themes;
+        let choice = theme::load_choice(&config_dir).unwrap_or_else(|err| {
+            problems.push(err.to_string());
+            None
+        });
+        // a picked theme whose file was removed since falls back to the first preset
+        self.theme_index = choice.and_then(|name| self.themes.iter().position(|theme| theme.name == name)).unwrap_or_default();
+        self.config_dir = Some(config_dir);
+        if !problems.is_empty() {
+            self.status_message = Some(problems.join(", "));
+        }
+    }
+
+    /// the colours of the current theme as far as the terminal can show them
+    pub fn color_scheme(&self) -> ColorScheme {
+        let colors = self.themes.get(self.theme_index).map(|theme| theme.colors).unwrap_or_else(|| theme::presets()[0].colors);
+        colors.adapted(self.color_support)
     }
-}
\ No newline at end of file
+
+    pub fn open_theme_picker(&mut self) {
+        self.theme_before_picking = self.theme_index;
+        self.return_screen = self.current_screen;
+        self.current_screen = CurrentScreen::PickingTheme;
+    }
+
+    /// the selected theme is drawn right away as a preview
+    pub fn select_previous_theme(&mut self) {
+        self.theme_index = self.theme_index.saturating_sub(1);
+    }
+
+    pub fn select_next_theme(&mut self) {
+        self.theme_index = next_index(Some(self.theme_index), self.themes.len()).unwrap_or_default();
+    }
+
+    /// keeps the previewed theme and remembers it for the next start
+    pub fn keep_theme(&mut self) {
+        self.current_screen = self.return_screen;
+        let Some(theme) = self.themes.get(self.theme_index) else {
+            return;
+        };
+        let saved = match &self.config_dir {
+            Some(config_dir) => theme::save_choice(config_dir, &theme.name),
+            None => Ok(()),
+        };
+        self.status_message = Some(match saved {
+            Ok(()) => format!("Using the {} theme", theme.name),
+            Err(err) => format!("Using the {} theme, could not save it: {err}", theme.name),
+        });
+    }
+
+    /// goes back to the theme used before the picker was opened
+    pub fn cancel_theme_picker(&mut self) {
+        self.theme_index = self.theme_before_picking;
+        self.current_screen = self.return_screen;
+    }
+
+    pub fn statistics(&self) -> Statistics {
+        let decks: Vec<&Deck> = match self.statistics_deck {
+            Some(index) => self.deck_family(index),
+            None => self.decks.iter().collect(),
+        };
+        Statistics::compute(&decks, &self.review_log, Utc::now())
+    }
+
+    /// switches the selected deck to the next scheduling algorithm
+    pub fn cycle_scheduler(&mut self) {
+        let before = self.snapshot_decks(self.selected_index);
+        if let Some(deck) = self.selected_index.and_then(|index| self.decks.get_mut(index)) {
+            deck.scheduler = deck.scheduler.next();
+            self.record_change(String::from("change of the scheduler"), before);
+        }
+    }
+
+    /// switches the card in the editor to the next direction, cloze notes ignore it
+    pub fn cycle_card_direction(&mut self) {
+        self.card_direction = self.card_direction.next();
+    }
+
+    /// switches the selected deck to the next way of answering its cards
+    pub fn cycle_review_mode(&mut self) {
+        let before = self.snapshot_decks(self.selected_index);
+        if let Some(deck) = self.selected_index.and_then(|index| self.decks.get_mut(index)) {
+            deck.review_mode = deck.review_mode.next();
+            self.record_change(String::from("change of the review mode"), before);
+        }
+    }
+
+    pub fn toggle_card_currently_editing(&mut self) {
+        self.card_currently_editing = Some(match self.card_currently_editing {
+            Some(EditorField::Front) => EditorField::Back,
+            Some(EditorField::Back) => EditorField::Tags,
+            Some(EditorField::Tags) | None => EditorField::Front,
+        });
+    }
+
+    /// the text field keys and pasted text go to, `None` on screens where keys aren't typed in as text
+    pub fn active_input_mut(&mut self) -> Option<&mut TextInput> {
+        let typing_answer = self.typing_answer();
+        match (self.current_screen, self.card_currently_editing) {
+            (CurrentScreen::AddingDeck, _) => Some(&mut self.name_input),
+            (CurrentScreen::EditingCard, Some(EditorField::Front)) => Some(&mut self.front_input),
+            (CurrentScreen::EditingCard, Some(EditorField::Back)) => Some(&mut self.back_input),
+            (CurrentScreen::EditingCard, Some(EditorField::Tags)) => Some(&mut self.tags_input),
+            (CurrentScreen::EnteringImportPath, _) => Some(&mut self.path_input),
+            (CurrentScreen::Searching, _) => Some(&mut self.search_input),
+            (CurrentScreen::EnteringQuery, _) => Some(&mut self.query_input),
+            (CurrentScreen::LearningMode, _) if typing_answer => Some(&mut self.answer_input),
+            (CurrentScreen::Browsing, _) => self.browser.as_mut().and_then(|browser| match browser.action {
+                Some(_) => Some(&mut browser.action_input),
+                None if browser.editing_query => Some(&mut browser.query_input),
+                None => None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// inserts pasted text into the field being typed in and updates what depends on it
+    pub fn paste(&mut self, text: &str) {
+        let Some(input) = self.active_input_mut() else {
+            return;
+        };
+        input.insert(text);
+        match self.current_screen {
+            CurrentScreen::Searching => self.update_search(),
+            CurrentScreen::EnteringQuery => self.update_query(),
+            CurrentScreen::Browsing => self.refresh_browser(),
+            _ => {}
+        }
+    }
+}
+
+/// the index after `index` in a list of `len` items, selecting the first item when nothing is selected yet
+fn next_index(index: Option<usize>, len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    match index {
+        Some(index) => Some((index + 1).min(len - 1)),
+        None => Some(0),
+    }
+}
+
+/// keeps `index` pointing into a list of `len` items after items were removed
+fn clamp_index(index: Option<usize>, len: usize) -> Option<usize> {
+    if len == 0 {
+        None
+    } else {
+        index.map(|index| index.min(len - 1))
+    }
+}
+
+/// the first line of a card side cut to a few words, to say which card a change was made to
+fn short_text(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    match line.char_indices().nth(24) {
+        Some((index, _)) => format!("\"{}...\"", &line[..index]),
+        None => format!("\"{line}\""),
+    }
+}
+
+/// replaces a leading `~` with the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn moved_index(index: usize, offset: isize, len: usize) -> Option<usize> {
+    let new_index = index.checked_add_signed(offset)?;
+    (index < len && new_index < len).then_some(new_index)
+}
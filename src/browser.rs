@@ -0,0 +1,287 @@
+use std::{cmp::Ordering, collections::BTreeSet};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    app::{Card, Deck, Guess},
+    query::{self, Query, QueryError},
+    review_log::ReviewLogEntry,
+    text_input::TextInput,
+};
+
+/// the columns of the card browser, in the order they are shown
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BrowserColumn {
+    Front,
+    Back,
+    Deck,
+    Tags,
+    Due,
+    Interval,
+    Ease,
+    Lapses,
+    LastGrade,
+}
+
+impl BrowserColumn {
+    pub const ALL: [BrowserColumn; 9] = [
+        BrowserColumn::Front,
+        BrowserColumn::Back,
+        BrowserColumn::Deck,
+        BrowserColumn::Tags,
+        BrowserColumn::Due,
+        BrowserColumn::Interval,
+        BrowserColumn::Ease,
+        BrowserColumn::Lapses,
+        BrowserColumn::LastGrade,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BrowserColumn::Front => "Front",
+            BrowserColumn::Back => "Back",
+            BrowserColumn::Deck => "Deck",
+            BrowserColumn::Tags => "Tags",
+            BrowserColumn::Due => "Due",
+            BrowserColumn::Interval => "Interval",
+            BrowserColumn::Ease => "Ease",
+            BrowserColumn::Lapses => "Lapses",
+            BrowserColumn::LastGrade => "Last grade",
+        }
+    }
+
+    /// the text shown in the column for `card`
+    pub fn cell(&self, deck: &Deck, card: &Card) -> String {
+        match self {
+            BrowserColumn::Front => card.front.replace('\n', " "),
+            BrowserColumn::Back => card.back.replace('\n', " "),
+            BrowserColumn::Deck => deck.name.clone(),
+            BrowserColumn::Tags => card.tags.iter().cloned().collect::<Vec<_>>().join(" "),
+            BrowserColumn::Due if card.suspended => String::from("suspended"),
+            BrowserColumn::Due if card.review.last_review.is_none() => String::from("new"),
+            BrowserColumn::Due => card.review.due.format("%Y-%m-%d").to_string(),
+            BrowserColumn::Interval => format!("{}d", card.review.interval),
+            BrowserColumn::Ease => format!("{:.2}", card.review.ease_factor),
+            BrowserColumn::Lapses => card.review.lapses.to_string(),
+            BrowserColumn::LastGrade => match card.last_guess {
+                Guess::Easy => String::from("easy"),
+                Guess::Correct => String::from("correct"),
+                Guess::Incorrect => String::from("incorrect"),
+                Guess::None => String::from("-"),
+            },
+        }
+    }
+
+    fn compare(&self, (deck_a, card_a): (&Deck, &Card), (deck_b, card_b): (&Deck, &Card)) -> Ordering {
+        let grade = |guess: Guess| match guess {
+            Guess::None => 0,
+            Guess::Incorrect => 1,
+            Guess::Correct => 2,
+            Guess::Easy => 3,
+        };
+        match self {
+            BrowserColumn::Front => card_a.front.to_lowercase().cmp(&card_b.front.to_lowercase()),
+            BrowserColumn::Back => card_a.back.to_lowercase().cmp(&card_b.back.to_lowercase()),
+            BrowserColumn::Deck => deck_a.name.to_lowercase().cmp(&deck_b.name.to_lowercase()),
+            BrowserColumn::Tags => card_a.tags.cmp(&card_b.tags),
+            BrowserColumn::Due => card_a.review.due.cmp(&card_b.review.due),
+            BrowserColumn::Interval => card_a.review.interval.cmp(&card_b.review.interval),
+            BrowserColumn::Ease => card_a.review.ease_factor.total_cmp(&card_b.review.ease_factor),
+            BrowserColumn::Lapses => card_a.review.lapses.cmp(&card_b.review.lapses),
+            BrowserColumn::LastGrade => grade(card_a.last_guess).cmp(&grade(card_b.last_guess)),
+        }
+    }
+}
+
+/// a change made to every marked card at once, the ones asking for text wait in `Browser::action`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BulkAction {
+    Move, // to the deck typed in
+    Tag,  // adds the typed in tags, the ones starting with `-` are removed
+}
+
+/// the card browser, a table of the cards of every deck matching a query
+pub struct Browser {
+    pub query_input: TextInput,
+    pub query_error: Option<QueryError>, // why the query can't be parsed, the last rows stay listed meanwhile
+    pub editing_query: bool, // whether keys go to the query instead of the table
+    pub rows: Vec<(usize, usize)>, // the deck and card index of each row, in the order they are shown
+    row_ids: Vec<u64>, // the card id of each row, to find the cards again after they moved
+    pub selected: usize, // the row the cursor is on
+    pub marked: BTreeSet<u64>, // the ids of the cards bulk actions apply to
+    pub sort_column: BrowserColumn,
+    pub sort_descending: bool,
+    pub action: Option<BulkAction>, // the bulk action whose text is being typed in
+    pub action_input: TextInput,
+}
+
+impl Browser {
+    pub fn new(query_input: String) -> Browser {
+        let mut input = TextInput::single_line();
+        input.set_text(&query_input);
+        Browser {
+            query_input: input,
+            query_error: None,
+            editing_query: false,
+            rows: Vec::new(),
+            row_ids: Vec::new(),
+            selected: 0,
+            marked: BTreeSet::new(),
+            sort_column: BrowserColumn::Deck,
+            sort_descending: false,
+            action: None,
+            action_input: TextInput::single_line(),
+        }
+    }
+
+    /// finds and sorts the matching cards again after the query or the cards changed, keeping the cursor on the same card
+    pub fn refresh(&mut self, decks: &[Deck], review_log: &[ReviewLogEntry], now: DateTime<Utc>) {
+        // the rows may point at other cards by now, only their ids are still right
+        let selected_id = self.row_ids.get(self.selected).copied();
+        match Query::parse(self.query_input.text()) {
+            Ok(query) => {
+                self.rows = query::find_cards(decks, review_log, &query, now);
+                self.query_error = None;
+            }
+            Err(err) => {
+                self.query_error = Some(err);
+                // the cards may have moved or been deleted, the ones still there are found again by id
+                self.rows = self.row_ids.iter().filter_map(|id| card_position(decks, *id)).collect();
+            }
+        }
+        self.sort(decks);
+        self.selected = selected_id
+            .and_then(|id| self.row_ids.iter().position(|row_id| *row_id == id))
+            .unwrap_or(self.selected)
+            .min(self.rows.len().saturating_sub(1));
+        // cards that are gone can't be marked
+        let ids: BTreeSet<u64> = self.row_ids.iter().copied().collect();
+        self.marked.retain(|id| ids.contains(id));
+    }
+
+    fn sort(&mut self, decks: &[Deck]) {
+        let column = self.sort_column;
+        let card = |(deck_index, card_index): (usize, usize)| (&decks[deck_index], &decks[deck_index].cards[card_index]);
+        // the sort is stable, so rows that compare equal stay in deck order
+        self.rows.sort_by(|a, b| column.compare(card(*a), card(*b)));
+        if self.sort_descending {
+            self.rows.reverse();
+        }
+        self.row_ids = self.rows.iter().map(|(deck_index, card_index)| decks[*deck_index].cards[*card_index].id).collect();
+    }
+
+    /// sorts by `column`, choosing the column it is already sorted by flips the order
+    pub fn sort_by(&mut self, column: BrowserColumn, decks: &[Deck]) {
+        if self.sort_column == column {
+            self.sort_descending = !self.sort_descending;
+        } else {
+            self.sort_column = column;
+            self.sort_descending = false;
+        }
+        let selected_id = self.row_ids.get(self.selected).copied();
+        self.sort(decks);
+        self.selected = selected_id.and_then(|id| self.row_ids.iter().position(|row_id| *row_id == id)).unwrap_or_default();
+    }
+
+    pub fn selected_row(&self) -> Option<(usize, usize)> {
+        self.rows.get(self.selected).copied()
+    }
+
+    pub fn selected_card<'a>(&self, decks: &'a [Deck]) -> Option<&'a Card> {
+        let (deck_index, card_index) = self.selected_row()?;
+        decks.get(deck_index)?.cards.get(card_index)
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.rows.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn is_marked(&self, decks: &[Deck], (deck_index, card_index): (usize, usize)) -> bool {
+        self.marked.contains(&decks[deck_index].cards[card_index].id)
+    }
+
+    /// marks or unmarks the card under the cursor and moves on to the next one
+    pub fn toggle_mark(&mut self, decks: &[Deck]) {
+        if let Some(card) = self.selected_card(decks) {
+            if !self.marked.remove(&card.id) {
+                self.marked.insert(card.id);
+            }
+            self.select_next();
+        }
+    }
+
+    /// marks every row, or clears the marks when they all are marked already
+    pub fn toggle_mark_all(&mut self) {
+        let ids: BTreeSet<u64> = self.row_ids.iter().copied().collect();
+        if self.marked == ids {
+            self.marked.clear();
+        } else {
+            self.marked = ids;
+        }
+    }
+
+    /// the ids of the cards a bulk action applies to, the marked ones or the one under the cursor when none are marked
+    pub fn targets(&self, decks: &[Deck]) -> BTreeSet<u64> {
+        if self.marked.is_empty() {
+            self.selected_card(decks).map(|card| card.id).into_iter().collect()
+        } else {
+            self.marked.clone()
+        }
+    }
+}
+
+/// the deck and card index of the card with `id`
+fn card_position(decks: &[Deck], id: u64) -> Option<(usize, usize)> {
+    decks.iter().enumerate().find_map(|(deck_index, deck)| {
+        deck.cards.iter().position(|card| card.id == id).map(|card_index| (deck_index, card_index))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deck(fronts: &[&str]) -> Deck {
+        Deck {
+            id: 0,
+            name: String::from("Spanish"),
+            cards: fronts.iter().map(|front| Card::new(front.to_string(), String::new())).collect(),
+            date_last_learned: Utc::now(),
+            scheduler: Default::default(),
+            review_mode: Default::default(),
+            answer_options: Default::default(),
+            collapsed: false,
+        }
+    }
+
+    fn fronts(browser: &Browser, decks: &[Deck]) -> Vec<String> {
+        browser.rows.iter().map(|(deck_index, card_index)| decks[*deck_index].cards[*card_index].front.clone()).collect()
+    }
+
+    #[test]
+    fn rows_follow_cards_while_the_query_is_invalid() {
+        let mut decks = vec![deck(&["a", "b", "c"])];
+        let mut browser = Browser::new(String::from("b or c"));
+        browser.refresh(&decks, &[], Utc::now());
+        browser.select_next();
+        assert_eq!(fronts(&browser, &decks), ["b", "c"]);
+
+        browser.query_input.set_text("b or (");
+        decks[0].cards.remove(0);
+        browser.refresh(&decks, &[], Utc::now());
+        assert!(browser.query_error.is_some());
+        assert_eq!(fronts(&browser, &decks), ["b", "c"]);
+        assert_eq!(browser.selected_card(&decks).map(|card| card.front.as_str()), Some("c"));
+
+        decks[0].cards.remove(0);
+        browser.refresh(&decks, &[], Utc::now());
+        assert_eq!(fronts(&browser, &decks), ["c"]);
+        assert_eq!(browser.selected, 0);
+    }
+}
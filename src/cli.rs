@@ -0,0 +1,376 @@
+use std::{
+    collections::BTreeSet,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use chrono::Utc;
+
+use crate::{
+    anki_import,
+    app::{App, Card, CardDirection, CardFace, Deck, Guess},
+    csv_import::{CsvImport, ImportTarget},
+    deck_tree,
+    export::{self, ExportFormat},
+    query::{self, Query},
+    stats::{format_duration, Statistics},
+};
+
+const USAGE: &str = "usage: ok-card [command]
+
+Without a command the terminal interface is started.
+
+commands:
+  add <deck> --front <text> --back <text> [--tag <tag>]...
+      [--direction front-back|back-front|both]
+                      add a card, creating the deck if it does not exist
+  list [<deck>] [--tag <tag>]...
+                      list the decks, or the cards of one deck
+  due [<deck>] [--tag <tag>]...
+                      list the cards that are due for review
+  import <file> [--deck <name>] [--delimiter <char>] [--header]
+         [--front <column>] [--back <column>] [--tags <column>]
+                      import a CSV/TSV file or an Anki .apkg/.colpkg package
+  export [<deck>] [--format csv|tsv|md|json|anki] [--output <file>]
+                      export all decks or one deck, to stdout without --output
+  stats [<deck>]      print review statistics
+  search <query>      list the cards matching a query like
+                      deck:Spanish tag:verbs is:due prop:ivl>30 added:7 rated:1:incorrect \"front:*ar\"
+  review [<deck>] [--tag <tag>]... [--query <query>]
+                      review the due cards in the terminal, or every card matching the query
+--tag limits a command to the cards with one of the given tags
+  help                show this message";
+
+/// the arguments of a subcommand, split into positional arguments and `--name value` options
+struct Arguments {
+    positional: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+/// options that don't take a value
+const FLAGS: [&str; 1] = ["--header"];
+
+impl Arguments {
+    fn parse(args: &[String]) -> Result<Arguments, String> {
+        let mut arguments = Arguments { positional: Vec::new(), options: Vec::new() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg.starts_with("--") {
+                let value = if FLAGS.contains(&arg.as_str()) {
+                    None
+                } else {
+                    Some(args.next().ok_or_else(|| format!("{arg} needs a value"))?.clone())
+                };
+                arguments.options.push((arg.clone(), value));
+            } else {
+                arguments.positional.push(arg.clone());
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options.iter().find(|(option, _)| option == name).and_then(|(_, value)| value.as_deref())
+    }
+
+    fn values(&self, name: &str) -> Vec<&str> {
+        self.options.iter().filter(|(option, _)| option == name).filter_map(|(_, value)| value.as_deref()).collect()
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    fn required(&self, name: &str) -> Result<&str, String> {
+        self.value(name).ok_or_else(|| format!("missing {name}"))
+    }
+
+    /// a 1-based column number turned into an index
+    fn column(&self, name: &str) -> Result<Option<usize>, String> {
+        self.value(name)
+            .map(|value| match value.parse::<usize>() {
+                Ok(column) if column > 0 => Ok(column - 1),
+                _ => Err(format!("{name} expects a column number starting at 1, got {value}")),
+            })
+            .transpose()
+    }
+
+    /// the tags given with `--tag`, cards need one of them to be included
+    fn tags(&self) -> BTreeSet<String> {
+        self.values("--tag").into_iter().map(String::from).collect()
+    }
+
+    fn check_options(&self, allowed: &[&str]) -> Result<(), String> {
+        match self.options.iter().find(|(option, _)| !allowed.contains(&option.as_str())) {
+            Some((option, _)) => Err(format!("unknown option {option}")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// runs the subcommand in `args` on the collection loaded into `app`
+pub fn run(app: &mut App, args: &[String]) -> Result<(), String> {
+    let (command, rest) = args.split_first().ok_or_else(|| String::from(USAGE))?;
+    let arguments = Arguments::parse(rest)?;
+
+    match command.as_str() {
+        "add" => add(app, &arguments)?,
+        "list" => list(app, &arguments)?,
+        "due" => due(app, &arguments)?,
+        "import" => import(app, &arguments)?,
+        "export" => export(app, &arguments)?,
+        "stats" => stats(app, &arguments)?,
+        "search" => search(app, &arguments)?,
+        "review" => review(app, &arguments)?,
+        "help" | "--help" | "-h" => println!("{USAGE}"),
+        _ => return Err(format!("unknown command {command}\n\n{USAGE}")),
+    }
+    Ok(())
+}
+
+/// writes a line of output, stopping quietly when the reading end of a pipe is closed like with `ok-card due | head`
+fn print_line(line: &str) -> Result<(), String> {
+    match writeln!(io::stdout(), "{line}") {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+        result => result.map_err(|err| format!("could not write output: {err}")),
+    }
+}
+
+fn save(app: &App) -> Result<(), String> {
+    app.save().map_err(|err| format!("could not save the collection: {err}"))
+}
+
+fn deck_index(app: &App, name: &str) -> Result<usize, String> {
+    app.decks.iter().position(|deck| deck.name == name).ok_or_else(|| format!("there is no deck called {name}"))
+}
+
+/// the deck named by the first positional argument with the decks nested in it, or every deck
+fn selected_decks<'a>(app: &'a App, arguments: &Arguments) -> Result<Vec<&'a Deck>, String> {
+    match arguments.positional.first() {
+        Some(name) => Ok(app.deck_family(deck_index(app, name)?)),
+        None => Ok(app.decks.iter().collect()),
+    }
+}
+
+fn add(app: &mut App, arguments: &Arguments) -> Result<(), String> {
+    arguments.check_options(&["--front", "--back", "--tag", "--direction"])?;
+    let name = arguments.positional.first().ok_or("missing the deck to add the card to")?;
+    let (front, back) = (arguments.required("--front")?.to_string(), arguments.required("--back")?.to_string());
+    let direction = match arguments.value("--direction").unwrap_or("front-back") {
+        "front-back" => CardDirection::FrontToBack,
+        "back-front" => CardDirection::BackToFront,
+        "both" => CardDirection::Both,
+        direction => return Err(format!("unknown direction {direction}, expected front-back, back-front or both")),
+    };
+
+    let index = match deck_index(app, name) {
+        Ok(index) => index,
+        Err(_) => {
+            app.add_deck(name.clone());
+            app.decks.len() - 1
+        }
+    };
+    app.decks[index].add_note(front, back, arguments.tags(), direction);
+    save(app)
+}
+
+fn list(app: &App, arguments: &Arguments) -> Result<(), String> {
+    arguments.check_options(&["--tag"])?;
+    let now = Utc::now();
+    let tags = arguments.tags();
+    match arguments.positional.first() {
+        Some(name) => {
+            for card in app.decks[deck_index(app, name)?].cards.iter().filter(|card| card.has_any_tag(&tags)) {
+                print_line(&format!("{}\t{}", card.front, card.back))?;
+            }
+        }
+        None => {
+            for deck in &app.decks {
+                let cards: Vec<&Card> = deck.cards.iter().filter(|card| card.has_any_tag(&tags)).collect();
+                let due = cards.iter().filter(|card| card.is_due(now)).count();
+                print_line(&format!("{}\t{} cards\t{} due", deck.name, cards.len(), due))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn due(app: &App, arguments: &Arguments) -> Result<(), String> {
+    arguments.check_options(&["--tag"])?;
+    let now = Utc::now();
+    let tags = arguments.tags();
+    for deck in selected_decks(app, arguments)? {
+        for card in deck.cards.iter().filter(|card| card.is_due(now) && card.has_any_tag(&tags)) {
+            print_line(&format!("{}\t{}\t{}", deck.name, card.question(), card.review.due.format("%Y-%m-%d %H:%M")))?;
+        }
+    }
+    Ok(())
+}
+
+fn import(app: &mut App, arguments: &Arguments) -> Result<(), String> {
+    arguments.check_options(&["--deck", "--delimiter", "--header", "--front", "--back", "--tags"])?;
+    let path = PathBuf::from(arguments.positional.first().ok_or("missing the file to import")?);
+
+    if matches!(path.extension().and_then(|extension| extension.to_str()), Some("apkg" | "colpkg")) {
+        let import = anki_import::import_package(&path).map_err(|err| format!("could not import {}: {err}", path.display()))?;
+        app.add_anki_import(import);
+        println!("{}", app.status_message.take().unwrap_or_default());
+        return save(app);
+    }
+
+    let mut import = CsvImport::open(&path, None, &app.decks).map_err(|err| format!("could not open {}: {err}", path.display()))?;
+    if let Some(name) = arguments.value("--deck") {
+        import.target = match deck_index(app, name) {
+            Ok(index) => ImportTarget::ExistingDeck(index),
+            Err(_) => ImportTarget::NewDeck(name.to_string()),
+        };
+    }
+    if let Some(delimiter) = arguments.value("--delimiter") {
+        import.options.delimiter = match delimiter {
+            "tab" | "\\t" => Some('\t'),
+            _ => {
+                let mut chars = delimiter.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(delimiter), None) => Some(delimiter),
+                    _ => return Err(format!("--delimiter expects a single character, got {delimiter}")),
+                }
+            }
+        };
+    }
+    import.options.has_header |= arguments.flag("--header");
+    import.options.front_column = arguments.column("--front")?.unwrap_or(import.options.front_column);
+    import.options.back_column = arguments.column("--back")?.unwrap_or(import.options.back_column);
+    import.options.tags_column = arguments.column("--tags")?.or(import.options.tags_column);
+    import.refresh(&app.decks);
+
+    for (line, reason) in &import.plan.skipped {
+        eprintln!("skipped line {line}: {reason:?}");
+    }
+    println!(
+        "imported {} cards, skipped {} rows ({} duplicates)",
+        import.plan.cards.len(),
+        import.plan.skipped.len(),
+        import.plan.duplicate_count()
+    );
+    app.csv_import = Some(import);
+    app.finish_import();
+    save(app)
+}
+
+fn export(app: &App, arguments: &Arguments) -> Result<(), String> {
+    arguments.check_options(&["--format", "--output"])?;
+    let format = match arguments.value("--format").unwrap_or("csv") {
+        "csv" => ExportFormat::Csv,
+        "tsv" => ExportFormat::Tsv,
+        "md" | "markdown" => ExportFormat::Markdown,
+        "json" => ExportFormat::Json,
+        "anki" => ExportFormat::AnkiText,
+        format => return Err(format!("unknown export format {format}, expected csv, tsv, md, json or anki")),
+    };
+    let decks = selected_decks(app, arguments)?;
+
+    match arguments.value("--output") {
+        Some(path) => export::export_to_file(Path::new(path), &decks, format).map_err(|err| format!("could not export: {err}")),
+        None => {
+            let output = export::export(&decks, format).map_err(|err| format!("could not export: {err}"))?;
+            print_line(output.trim_end_matches('\n'))
+        }
+    }
+}
+
+fn stats(app: &App, arguments: &Arguments) -> Result<(), String> {
+    arguments.check_options(&[])?;
+    let statistics = Statistics::compute(&selected_decks(app, arguments)?, &app.review_log, Utc::now());
+
+    print_line(&format!("cards: {}", statistics.total_cards))?;
+    print_line(&format!("reviews: {} ({} today)", statistics.total_reviews, statistics.reviews_today))?;
+    match statistics.retention {
+        Some(retention) => print_line(&format!("retention: {:.0}%", retention * 100.0))?,
+        None => print_line("retention: no reviews yet")?,
+    }
+    print_line(&format!("time spent: {}", format_duration(statistics.total_time_spent_ms)))?;
+    print_line(&format!("due today: {}", statistics.due_forecast[0]))?;
+    print_line(&format!("due this week: {}", statistics.due_forecast.iter().take(7).sum::<u64>()))?;
+    Ok(())
+}
+
+/// the deck and card index of the cards matching `text`, a query that can't be parsed is shown with a caret under the mistake
+fn query_cards(app: &App, text: &str) -> Result<Vec<(usize, usize)>, String> {
+    let query = Query::parse(text).map_err(|err| format!("invalid query: {}\n{}", err.message, err.pointer(text)))?;
+    Ok(query::find_cards(&app.decks, &app.review_log, &query, Utc::now()))
+}
+
+fn search(app: &App, arguments: &Arguments) -> Result<(), String> {
+    arguments.check_options(&[])?;
+    // the query can be given as one argument or spread over several
+    for (deck_index, card_index) in query_cards(app, &arguments.positional.join(" "))? {
+        let deck = &app.decks[deck_index];
+        let card = &deck.cards[card_index];
+        print_line(&format!("{}\t{}\t{}", deck.name, card.front, card.back))?;
+    }
+    Ok(())
+}
+
+/// a plain text review session reading answers from stdin, so it also works over ssh or in a pipe
+fn review(app: &mut App, arguments: &Arguments) -> Result<(), String> {
+    arguments.check_options(&["--tag", "--query"])?;
+    app.tag_filter = arguments.tags();
+    // studying a deck includes the decks nested in it, so only the top level decks are studied when none is given
+    let mut deck_indices: Vec<usize> = match arguments.positional.first() {
+        Some(name) => vec![deck_index(app, name)?],
+        None => (0..app.decks.len()).filter(|index| deck_tree::parent_name(&app.decks[*index].name).is_none()).collect(),
+    };
+    if let Some(text) = arguments.value("--query") {
+        let cards = query_cards(app, text)?;
+        app.custom_study = Some(cards.iter().map(|(deck_index, card_index)| app.decks[*deck_index].cards[*card_index].id).collect());
+        // a custom study session goes through all the matching cards at once
+        deck_indices.truncate(1);
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut prompt = |text: &str| -> Option<String> {
+        print!("{text}");
+        io::stdout().flush().ok()?;
+        lines.next()?.ok()
+    };
+
+    let mut reviewed = 0;
+    for deck_index in deck_indices {
+        app.selected_index = Some(deck_index);
+        app.next_card_to_learn();
+
+        while let Some((card_deck_index, card_index)) = app.card_currently_learning {
+            let card = &app.decks[card_deck_index].cards[card_index];
+            println!("\n[{}] {}", app.decks[card_deck_index].name, card.question());
+            if prompt("press enter to show the answer ").is_none() {
+                return finish_review(app, reviewed);
+            }
+            println!("{}", card.answer());
+            app.face_showing = Some(CardFace::CardBack);
+
+            let guess = loop {
+                let Some(answer) = prompt("(h) incorrect / (j) correct / (k) easy / (q) quit: ") else {
+                    return finish_review(app, reviewed);
+                };
+                match answer.trim() {
+                    "h" => break Guess::Incorrect,
+                    "j" => break Guess::Correct,
+                    "k" => break Guess::Easy,
+                    "q" => return finish_review(app, reviewed),
+                    _ => {}
+                }
+            };
+            app.answer_card(guess);
+            reviewed += 1;
+            save(app)?;
+        }
+    }
+    finish_review(app, reviewed)
+}
+
+fn finish_review(app: &App, reviewed: usize) -> Result<(), String> {
+    println!("\nreviewed {reviewed} cards");
+    save(app)
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeSet;
+
+/// a `{{cN::answer::hint}}` deletion found in cloze text
+struct Deletion<'a> {
+    start: usize,
+    end: usize,
+    index: u32,
+    answer: &'a str,
+    hint: &'a str,
+}
+
+/// finds the deletions in `text` in the order they appear
+fn deletions(text: &str) -> Vec<Deletion<'_>> {
+    let mut deletions = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = text[offset..].find("{{c").map(|start| start + offset) {
+        let Some(end) = text[start..].find("}}").map(|end| start + end + 2) else {
+            break;
+        };
+        let inner = &text[start + 3..end - 2];
+        offset = end;
+
+        let Some((number, content)) = inner.split_once("::") else {
+            continue;
+        };
+        let Ok(index) = number.parse() else {
+            continue;
+        };
+        let (answer, hint) = content.split_once("::").unwrap_or((content, ""));
+        deletions.push(Deletion { start, end, index, answer, hint });
+    }
+    deletions
+}
+
+/// the cloze numbers used in `text`, each of them becomes its own card
+pub fn cloze_indices(text: &str) -> BTreeSet<u32> {
+    deletions(text).iter().map(|deletion| deletion.index).filter(|index| *index > 0).collect()
+}
+
+pub fn is_cloze(text: &str) -> bool {
+    !cloze_indices(text).is_empty()
+}
+
+/// the hidden text of deletion `index`, in the order it appears
+pub fn cloze_answers(text: &str, index: u32) -> Vec<&str> {
+    deletions(text).into_iter().filter(|deletion| deletion.index == index).map(|deletion| deletion.answer).collect()
+}
+
+/// replaces the deletions of `text`, hiding deletion `active` behind `[...]` or its hint unless the answer is revealed
+pub fn render_cloze(text: &str, active: u32, reveal: bool) -> String {
+    let mut output = String::new();
+    let mut position = 0;
+
+    for deletion in deletions(text) {
+        output.push_str(&text[position..deletion.start]);
+        if deletion.index == active && !reveal {
+            output.push_str(&format!("[{}]", if deletion.hint.is_empty() { "..." } else { deletion.hint }));
+        } else {
+            output.push_str(deletion.answer);
+        }
+        position = deletion.end;
+    }
+    output.push_str(&text[position..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finding_deletions() {
+        let text = "{{c1::Madrid}} is in {{c2::Spain::country}}, {{c1::Europe}}";
+        let found: Vec<(u32, &str, &str)> =
+            deletions(text).iter().map(|deletion| (deletion.index, deletion.answer, deletion.hint)).collect();
+        assert_eq!(found, vec![(1, "Madrid", ""), (2, "Spain", "country"), (1, "Europe", "")]);
+        assert_eq!(cloze_indices(text), BTreeSet::from([1, 2]));
+        assert_eq!(cloze_answers(text, 1), vec!["Madrid", "Europe"]);
+    }
+
+    #[test]
+    fn not_deletions() {
+        let cases = ["plain text", "{{Front}}", "{{cx::nope}}", "{{c1}}", "{{c0::zero}}", "{{c1::unclosed"];
+        for text in cases {
+            assert!(!is_cloze(text), "{text}");
+        }
+    }
+
+    #[test]
+    fn rendering() {
+        let text = "{{c1::Madrid}} is in {{c2::Spain::country}}";
+        let cases = [
+            (1, false, "[...] is in Spain"),
+            (2, false, "Madrid is in [country]"),
+            (1, true, "Madrid is in Spain"),
+            (3, false, "Madrid is in Spain"),
+        ];
+        for (active, reveal, rendered) in cases {
+            assert_eq!(render_cloze(text, active, reveal), rendered, "{active} {reveal}");
+        }
+        assert_eq!(render_cloze("no deletions", 1, false), "no deletions");
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    app::{Card, Deck},
+    cloze::is_cloze,
+};
+
+/// the delimiters that can be chosen in the import preview, `None` detects it from the file
+pub const DELIMITERS: [Option<char>; 4] = [None, Some(','), Some('\t'), Some(';')];
+
+/// how the rows of a delimited file are turned into cards
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CsvOptions {
+    pub delimiter: Option<char>, // `None` detects the delimiter from the first line
+    pub has_header: bool,
+    pub front_column: usize,
+    pub back_column: usize,
+    pub tags_column: Option<usize>, // a column of tags separated by spaces
+}
+
+impl Default for CsvOptions {
+    fn default() -> CsvOptions {
+        CsvOptions {
+            delimiter: None,
+            has_header: false,
+            front_column: 0,
+            back_column: 1,
+            tags_column: None,
+        }
+    }
+}
+
+/// where the imported cards end up
+#[derive(Clone, PartialEq, Debug)]
+pub enum ImportTarget {
+    NewDeck(String),
+    ExistingDeck(usize),
+}
+
+/// why a row of the file is not imported
+#[derive(Clone, PartialEq, Debug)]
+pub enum SkipReason {
+    MissingColumn,
+    EmptyField,
+    Duplicate,
+}
+
+/// the cards a file would produce with the current options, shown in the preview before importing
+#[derive(Default, Debug)]
+pub struct ImportPlan {
+    pub cards: Vec<(String, String, BTreeSet<String>)>,
+    pub skipped: Vec<(usize, SkipReason)>, // the line number of every row that is not imported
+    pub column_count: usize,
+}
+
+impl ImportPlan {
+    pub fn duplicate_count(&self) -> usize {
+        self.skipped.iter().filter(|(_, reason)| *reason == SkipReason::Duplicate).count()
+    }
+}
+
+/// a file being imported from the preview screen
+pub struct CsvImport {
+    pub path: PathBuf,
+    contents: String,
+    pub options: CsvOptions,
+    pub target: ImportTarget,
+    pub plan: ImportPlan,
+}
+
+impl CsvImport {
+    pub fn open(path: &Path, target: Option<usize>, decks: &[Deck]) -> io::Result<CsvImport> {
+        let contents = fs::read_to_string(path)?;
+        let target = match target {
+            Some(index) => ImportTarget::ExistingDeck(index),
+            None => ImportTarget::NewDeck(deck_name_for(path)),
+        };
+        let mut import = CsvImport {
+            path: path.to_path_buf(),
+            options: detect_columns(&contents),
+            contents,
+            target,
+            plan: ImportPlan::default(),
+        };
+        import.refresh(decks);
+        Ok(import)
+    }
+
+    /// recomputes the plan after the options or the target changed
+    pub fn refresh(&mut self, decks: &[Deck]) {
+        let existing = match self.target {
+            ImportTarget::ExistingDeck(index) => decks.get(index).map(|deck| deck.cards.as_slice()).unwrap_or_default(),
+            // the cards go into the deck of that name when there already is one
+            ImportTarget::NewDeck(ref name) => {
+                decks.iter().find(|deck| deck.name == *name).map(|deck| deck.cards.as_slice()).unwrap_or_default()
+            }
+        };
+        self.plan = plan_import(&self.contents, &self.options, existing);
+    }
+
+    pub fn delimiter(&self) -> char {
+        self.options.delimiter.unwrap_or_else(|| detect_delimiter(&self.contents))
+    }
+
+    pub fn cycle_delimiter(&mut self) {
+        let position = DELIMITERS.iter().position(|delimiter| *delimiter == self.options.delimiter).unwrap_or_default();
+        self.options.delimiter = DELIMITERS[(position + 1) % DELIMITERS.len()];
+    }
+
+    pub fn cycle_front_column(&mut self) {
+        self.options.front_column = (self.options.front_column + 1) % self.plan.column_count.max(1);
+    }
+
+    pub fn cycle_back_column(&mut self) {
+        self.options.back_column = (self.options.back_column + 1) % self.plan.column_count.max(1);
+    }
+
+    pub fn cycle_tags_column(&mut self) {
+        self.options.tags_column = match self.options.tags_column {
+            None => Some(0),
+            Some(column) if column + 1 < self.plan.column_count => Some(column + 1),
+            Some(_) => None,
+        };
+    }
+
+    /// switches between a new deck and each of the existing decks
+    pub fn cycle_target(&mut self, decks: &[Deck]) {
+        self.target = match self.target {
+            ImportTarget::NewDeck(_) if !decks.is_empty() => ImportTarget::ExistingDeck(0),
+            ImportTarget::ExistingDeck(index) if index + 1 < decks.len() => ImportTarget::ExistingDeck(index + 1),
+            _ => ImportTarget::NewDeck(deck_name_for(&self.path)),
+        };
+    }
+}
+
+/// new decks are named after the imported file
+fn deck_name_for(path: &Path) -> String {
+    path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_else(|| String::from("Imported"))
+}
+
+/// files with a `front` and `back` header, like the ones exported by ok-card, have their columns picked from the header
+pub fn detect_columns(contents: &str) -> CsvOptions {
+    let options = CsvOptions::default();
+    let Some((_, header)) = parse_delimited(contents, detect_delimiter(contents)).into_iter().next() else {
+        return options;
+    };
+    let column = |name: &str| header.iter().position(|field| field.trim().eq_ignore_ascii_case(name));
+    match (column("front"), column("back")) {
+        (Some(front_column), Some(back_column)) => CsvOptions {
+            has_header: true,
+            front_column,
+            back_column,
+            tags_column: column("tags"),
+            ..options
+        },
+        _ => options,
+    }
+}
+
+/// tabs win over semicolons, which win over commas, counted on the first line
+pub fn detect_delimiter(contents: &str) -> char {
+    let first_line = contents.lines().next().unwrap_or_default();
+    if first_line.contains('\t') {
+        '\t'
+    } else if first_line.matches(';').count() > first_line.matches(',').count() {
+        ';'
+    } else {
+        ','
+    }
+}
+
+/// splits delimited text into rows of fields, each with the line number it starts on.
+/// Fields can be quoted with `"` to contain delimiters and newlines, `""` inside quotes is a literal quote.
+pub fn parse_delimited(contents: &str, delimiter: char) -> Vec<(usize, Vec<String>)> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push((row_line, std::mem::take(&mut row)));
+                line += 1;
+                row_line = line;
+            }
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((row_line, row));
+    }
+
+    // blank lines are not rows
+    rows.retain(|(_, row)| !(row.len() == 1 && row[0].trim().is_empty()));
+    rows
+}
+
+/// works out which rows become cards, skipping incomplete rows and fronts that already exist
+pub fn plan_import(contents: &str, options: &CsvOptions, existing: &[Card]) -> ImportPlan {
+    let delimiter = options.delimiter.unwrap_or_else(|| detect_delimiter(contents));
+    let rows = parse_delimited(contents, delimiter);
+    let mut plan = ImportPlan {
+        column_count: rows.iter().map(|(_, row)| row.len()).max().unwrap_or_default(),
+        ..ImportPlan::default()
+    };
+    let mut seen_fronts: HashSet<String> = existing.iter().map(|card| card.front.trim().to_string()).collect();
+
+    for (line, row) in rows.into_iter().skip(options.has_header as usize) {
+        let (Some(front), Some(back)) = (row.get(options.front_column), row.get(options.back_column)) else {
+            plan.skipped.push((line, SkipReason::MissingColumn));
+            continue;
+        };
+        let (front, back) = (front.trim(), back.trim());
+        // the back of a cloze note only holds extra notes, so it may be empty
+        if front.is_empty() || (back.is_empty() && !is_cloze(front)) {
+            plan.skipped.push((line, SkipReason::EmptyField));
+            continue;
+        }
+        if !seen_fronts.insert(front.to_string()) {
+            plan.skipped.push((line, SkipReason::Duplicate));
+            continue;
+        }
+        let tags = options
+            .tags_column
+            .and_then(|column| row.get(column))
+            .map(|tags| tags.split_whitespace().map(String::from).collect())
+            .unwrap_or_default();
+        plan.cards.push((front.to_string(), back.to_string(), tags));
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Rows = Vec<(usize, Vec<String>)>;
+
+    fn fields(row: &[&str]) -> Vec<String> {
+        row.iter().map(|field| field.to_string()).collect()
+    }
+
+    #[test]
+    fn delimiter_detection() {
+        let cases = [
+            ("front,back\n", ','),
+            ("front\tback,with comma\n", '\t'),
+            ("a;b;c,d\n", ';'),
+            ("a;b,c,d\n", ','),
+            ("no delimiter\n", ','),
+            ("", ','),
+        ];
+        for (contents, delimiter) in cases {
+            assert_eq!(detect_delimiter(contents), delimiter, "{contents:?}");
+        }
+    }
+
+    #[test]
+    fn delimited_rows() {
+        let cases: [(&str, char, Rows); 7] = [
+            ("a,b\nc,d", ',', vec![(1, fields(&["a", "b"])), (2, fields(&["c", "d"]))]),
+            ("a,b\r\nc,d\r\n", ',', vec![(1, fields(&["a", "b"])), (2, fields(&["c", "d"]))]),
+            ("a\tb,c\n", '\t', vec![(1, fields(&["a", "b,c"]))]),
+            ("\"a,b\",c\n", ',', vec![(1, fields(&["a,b", "c"]))]),
+            ("\"say \"\"hi\"\"\",x\n", ',', vec![(1, fields(&["say \"hi\"", "x"]))]),
+            ("\"two\nlines\",x\ny,z\n", ',', vec![(1, fields(&["two\nlines", "x"])), (3, fields(&["y", "z"]))]),
+            ("a,b\n\n  \nc,\n", ',', vec![(1, fields(&["a", "b"])), (4, fields(&["c", ""]))]),
+        ];
+        for (contents, delimiter, rows) in cases {
+            assert_eq!(parse_delimited(contents, delimiter), rows, "{contents:?}");
+        }
+    }
+
+    #[test]
+    fn header_columns() {
+        let options = detect_columns("tags;Back;front\nx;b;f\n");
+        assert!(options.has_header);
+        assert_eq!((options.front_column, options.back_column, options.tags_column), (2, 1, Some(0)));
+        assert_eq!(detect_columns("hola,hello\n"), CsvOptions::default());
+    }
+
+    #[test]
+    fn skipped_rows() {
+        let existing = [Card::new(String::from("uno"), String::from("one"))];
+        let contents = "hola,hello,greeting\nuno,one\nadios\nvale,\nhola,hi\n";
+        let options = CsvOptions { tags_column: Some(2), ..CsvOptions::default() };
+        let plan = plan_import(contents, &options, &existing);
+        assert_eq!(plan.column_count, 3);
+        assert_eq!(plan.cards.len(), 1);
+        assert_eq!(plan.cards[0].2, BTreeSet::from([String::from("greeting")]));
+        assert_eq!(
+            plan.skipped,
+            vec![(2, SkipReason::Duplicate), (3, SkipReason::MissingColumn), (4, SkipReason::EmptyField), (5, SkipReason::Duplicate)]
+        );
+        assert_eq!(plan.duplicate_count(), 2);
+    }
+}
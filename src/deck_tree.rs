@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::app::Deck;
+
+/// separates the names of parent and child decks, like in `Languages::Spanish::Verbs`
+pub const SEPARATOR: &str = "::";
+
+/// the name of the deck `name` is nested in, `None` for top level decks
+pub fn parent_name(name: &str) -> Option<&str> {
+    name.rsplit_once(SEPARATOR).map(|(parent, _)| parent)
+}
+
+/// the last part of the name, shown in the tree under its parent
+pub fn short_name(name: &str) -> &str {
+    name.rsplit_once(SEPARATOR).map_or(name, |(_, short)| short)
+}
+
+pub fn is_descendant(name: &str, ancestor: &str) -> bool {
+    name.len() > ancestor.len() + SEPARATOR.len() && name.starts_with(ancestor) && name[ancestor.len()..].starts_with(SEPARATOR)
+}
+
+/// the names of the decks `name` is nested in, outermost first
+pub fn ancestor_names(name: &str) -> Vec<&str> {
+    name.match_indices(SEPARATOR).map(|(position, _)| &name[..position]).collect()
+}
+
+/// the deck at `index` followed by the indices of all the decks nested in it
+pub fn family(decks: &[Deck], index: usize) -> Vec<usize> {
+    let Some(deck) = decks.get(index) else {
+        return Vec::new();
+    };
+    let mut indices = vec![index];
+    indices.extend((0..decks.len()).filter(|other| is_descendant(&decks[*other].name, &deck.name)));
+    indices
+}
+
+/// a deck shown in the deck tree
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TreeRow {
+    pub index: usize,
+    pub depth: usize,
+    pub has_children: bool,
+}
+
+/// the decks in the order they are shown in the tree, children under their parent in the order of `decks`.
+/// The children of collapsed decks are left out, decks whose parent doesn't exist are shown at the top level.
+pub fn tree_rows(decks: &[Deck]) -> Vec<TreeRow> {
+    // decks sharing a name are one node, the first of them gets the children
+    let mut first_with_name: HashMap<&str, usize> = HashMap::new();
+    for (index, deck) in decks.iter().enumerate() {
+        first_with_name.entry(deck.name.as_str()).or_insert(index);
+    }
+    let parent = |index: usize| parent_name(&decks[index].name).and_then(|name| first_with_name.get(name).copied());
+
+    let mut children: HashMap<Option<usize>, Vec<usize>> = HashMap::new();
+    for index in 0..decks.len() {
+        children.entry(parent(index)).or_default().push(index);
+    }
+
+    let mut rows = Vec::new();
+    let mut stack: Vec<(usize, usize)> = children.get(&None).into_iter().flatten().rev().map(|index| (*index, 0)).collect();
+    while let Some((index, depth)) = stack.pop() {
+        let deck_children = children.get(&Some(index)).map(Vec::as_slice).unwrap_or_default();
+        rows.push(TreeRow { index, depth, has_children: !deck_children.is_empty() });
+        if !decks[index].collapsed {
+            stack.extend(deck_children.iter().rev().map(|child| (*child, depth + 1)));
+        }
+    }
+    rows
+}
+
+/// the number of cards of a deck and all the decks nested in it
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct DeckCounts {
+    pub new: usize, // never reviewed
+    pub due: usize, // reviewed before and due again
+    pub total: usize,
+}
+
+pub fn deck_counts(decks: &[Deck], index: usize, now: DateTime<Utc>) -> DeckCounts {
+    let mut counts = DeckCounts::default();
+    for card in family(decks, index).into_iter().flat_map(|index| decks[index].cards.iter()) {
+        counts.total += 1;
+        if card.suspended {
+            continue;
+        }
+        if card.review.last_review.is_none() {
+            counts.new += 1;
+        } else if card.is_due(now) {
+            counts.due += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::app::{App, Card};
+
+    fn decks(names: &[&str]) -> Vec<Deck> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| Deck {
+                id: index as u64,
+                name: name.to_string(),
+                cards: Vec::new(),
+                date_last_learned: Utc::now(),
+                scheduler: Default::default(),
+                review_mode: Default::default(),
+                answer_options: Default::default(),
+                collapsed: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn names() {
+        let cases = [
+            ("a::b", "a", true),
+            ("a::b::c", "a", true),
+            ("a::b::c", "a::b", true),
+            ("ab", "a", false),
+            ("a:b", "a", false),
+            ("a", "a", false),
+            ("a::", "a", false),
+            ("b::a", "a", false),
+        ];
+        for (name, ancestor, descendant) in cases {
+            assert_eq!(is_descendant(name, ancestor), descendant, "{name} {ancestor}");
+        }
+        assert_eq!(parent_name("a::b::c"), Some("a::b"));
+        assert_eq!(parent_name("a"), None);
+        assert_eq!(short_name("a::b::c"), "c");
+        assert_eq!(ancestor_names("a::b::c"), ["a", "a::b"]);
+    }
+
+    #[test]
+    fn families() {
+        let decks = decks(&["a", "ab", "a::b", "a::b::c", "b"]);
+        assert_eq!(family(&decks, 0), [0, 2, 3]);
+        assert_eq!(family(&decks, 1), [1]);
+        assert_eq!(family(&decks, 2), [2, 3]);
+        assert_eq!(family(&decks, 9), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn rows() {
+        let mut decks = decks(&["b::x", "a", "b", "a::y", "orphan::z", "a::y::w"]);
+        fn shown(decks: &[Deck]) -> Vec<(&str, usize, bool)> {
+            tree_rows(decks).iter().map(|row| (decks[row.index].name.as_str(), row.depth, row.has_children)).collect()
+        }
+        assert_eq!(
+            shown(&decks),
+            [("a", 0, true), ("a::y", 1, true), ("a::y::w", 2, false), ("b", 0, true), ("b::x", 1, false), ("orphan::z", 0, false)]
+        );
+        decks[1].collapsed = true;
+        assert_eq!(shown(&decks), [("a", 0, true), ("b", 0, true), ("b::x", 1, false), ("orphan::z", 0, false)]);
+    }
+
+    #[test]
+    fn nested_counts() {
+        let now = Utc::now();
+        let mut decks = decks(&["a", "a::b", "ab"]);
+        let card = |due: Option<i64>, suspended: bool| {
+            let mut card = Card::new(String::from("front"), String::from("back"));
+            if let Some(days) = due {
+                card.review.last_review = Some(now - Duration::days(10));
+                card.review.due = now + Duration::days(days);
+            }
+            card.suspended = suspended;
+            card
+        };
+        decks[0].cards = vec![card(None, false), card(Some(-1), false)];
+        decks[1].cards = vec![card(Some(-2), false), card(Some(3), false), card(None, true)];
+        decks[2].cards = vec![card(None, false)];
+
+        assert_eq!(deck_counts(&decks, 0, now), DeckCounts { new: 1, due: 2, total: 5 });
+        assert_eq!(deck_counts(&decks, 1, now), DeckCounts { new: 0, due: 1, total: 3 });
+        assert_eq!(deck_counts(&decks, 2, now), DeckCounts { new: 1, due: 0, total: 1 });
+    }
+
+    #[test]
+    fn renaming_a_parent() {
+        let mut app = App::new();
+        app.decks = decks(&["a", "a::b", "a::b::c", "ab", "x"]);
+        let rename = |app: &mut App, index: usize, name: &str| {
+            app.selected_index = Some(index);
+            app.start_renaming_deck();
+            app.name_input.set_text(name);
+            app.save_deck_name()
+        };
+        let names = |app: &App| app.decks.iter().map(|deck| deck.name.clone()).collect::<Vec<_>>();
+
+        assert!(rename(&mut app, 0, "z"));
+        assert_eq!(names(&app), ["z", "z::b", "z::b::c", "ab", "x"]);
+        // the nested decks would take the name of an existing deck
+        app.decks.push(decks(&["q::b"]).remove(0));
+        assert!(!rename(&mut app, 0, "q"));
+        assert_eq!(names(&app)[..3], ["z", "z::b", "z::b::c"]);
+        assert!(!rename(&mut app, 1, "ab"));
+        assert!(rename(&mut app, 1, "x::b"));
+        assert_eq!(names(&app)[..3], ["z", "x::b", "x::b::c"]);
+    }
+}
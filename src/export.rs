@@ -0,0 +1,347 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{Card, CardKind, Deck},
+    scheduler::SchedulerKind,
+};
+
+/// version of the json export schema, bumped whenever a field is removed or changes meaning
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    Markdown,
+    Json,
+    AnkiText,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 5] = [
+        ExportFormat::Csv,
+        ExportFormat::Tsv,
+        ExportFormat::Markdown,
+        ExportFormat::Json,
+        ExportFormat::AnkiText,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Tsv => "TSV",
+            ExportFormat::Markdown => "Markdown table",
+            ExportFormat::Json => "JSON",
+            ExportFormat::AnkiText => "Anki text import",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Tsv => "tsv",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::AnkiText => "txt",
+        }
+    }
+}
+
+/// the json export, kept separate from the collection file so its layout only changes with the schema version
+#[derive(Serialize, Deserialize)]
+pub struct JsonExport {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub decks: Vec<JsonDeck>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsonDeck {
+    pub name: String,
+    pub scheduler: SchedulerKind,
+    pub cards: Vec<JsonCard>,
+}
+
+/// a card with everything needed to restore it, the fields after `lapses` were added later in version 1 and may be missing
+#[derive(Serialize, Deserialize)]
+pub struct JsonCard {
+    pub front: String,
+    pub back: String,
+    pub tags: BTreeSet<String>,
+    pub due: DateTime<Utc>,
+    pub interval: u32,
+    pub ease_factor: f64,
+    pub repetitions: u32,
+    pub lapses: u32,
+    #[serde(default)]
+    pub id: Option<u64>,
+    #[serde(default)]
+    pub kind: CardKind, // reverse and cloze cards share their front and back with the other cards of their note
+    #[serde(default)]
+    pub note_id: Option<u64>,
+    #[serde(default)]
+    pub suspended: bool,
+    #[serde(default)]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_review: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub leitner_box: u8,
+    #[serde(default)]
+    pub stability: f64, // FSRS memory state, 0 when the card was never reviewed with FSRS
+    #[serde(default)]
+    pub difficulty: f64,
+}
+
+/// the file the decks are exported to when no path is given, named after the deck when there is only one
+pub fn default_export_path(decks: &[&Deck], format: ExportFormat) -> PathBuf {
+    let name = match decks {
+        [deck] => deck.name.replace(['/', '\\', ':'], "_"),
+        _ => String::from("ok-card-export"),
+    };
+    PathBuf::from(format!("{}.{}", name, format.extension()))
+}
+
+pub fn export_to_file(path: &Path, decks: &[&Deck], format: ExportFormat) -> io::Result<()> {
+    fs::write(path, export(decks, format)?)
+}
+
+/// exports to `path`, or to `name-2.ext`, `name-3.ext` and so on when that file already exists,
+/// returning the path that was written. Existing files are never overwritten.
+pub fn export_to_new_file(path: &Path, decks: &[&Deck], format: ExportFormat) -> io::Result<PathBuf> {
+    let contents = export(decks, format)?;
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    for number in 1.. {
+        let candidate = if number == 1 {
+            path.to_path_buf()
+        } else {
+            path.with_file_name(format!("{stem}-{number}.{}", format.extension()))
+        };
+        match OpenOptions::new().write(true).create_new(true).open(&candidate) {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())?;
+                return Ok(candidate);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    unreachable!("there is always a free file name")
+}
+
+/// the Anki note type a note of `deck` is imported as, going by the kinds of the cards it generated
+fn anki_note_type(deck: &Deck, card: &Card) -> &'static str {
+    let siblings = || deck.cards.iter().filter(|sibling| card.note_id.is_some() && sibling.note_id == card.note_id);
+    match card.kind {
+        CardKind::Cloze(_) => "Cloze",
+        _ if siblings().any(|sibling| sibling.kind == CardKind::Reverse) && siblings().any(|sibling| sibling.kind == CardKind::Basic) => {
+            "Basic (and reversed card)"
+        }
+        _ => "Basic",
+    }
+}
+
+/// renders the cards of `decks` in `format`. The text formats get one row per note,
+/// since the cards of a cloze or bidirectional note share their front and back.
+pub fn export(decks: &[&Deck], format: ExportFormat) -> io::Result<String> {
+    let mut exported_notes = HashSet::new();
+    let rows: Vec<[String; 5]> = decks
+        .iter()
+        .flat_map(|deck| deck.cards.iter().map(move |card| (deck, card)))
+        .filter(|(_, card)| card.note_id.is_none_or(|note_id| exported_notes.insert(note_id)))
+        .map(|(deck, card)| {
+            let tags = card.tags.iter().cloned().collect::<Vec<_>>().join(" ");
+            [deck.name.clone(), card.front.clone(), card.back.clone(), tags, anki_note_type(deck, card).to_string()]
+        })
+        .collect();
+
+    let output = match format {
+        ExportFormat::Csv | ExportFormat::Tsv => {
+            let delimiter = if format == ExportFormat::Csv { ',' } else { '\t' };
+            let mut output = delimited_row(&["deck", "front", "back", "tags"], delimiter);
+            for row in rows {
+                output.push_str(&delimited_row(&row[..4], delimiter));
+            }
+            output
+        }
+        ExportFormat::Markdown => {
+            let mut output = String::from("| Deck | Front | Back | Tags |\n| --- | --- | --- | --- |\n");
+            for row in rows {
+                let cells: Vec<String> = row[..4].iter().map(|cell| markdown_cell(cell)).collect();
+                output.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+            output
+        }
+        ExportFormat::Json => {
+            let export = JsonExport {
+                format: String::from("ok-card-export"),
+                version: EXPORT_SCHEMA_VERSION,
+                exported_at: Utc::now(),
+                decks: decks
+                    .iter()
+                    .map(|deck| JsonDeck {
+                        name: deck.name.clone(),
+                        scheduler: deck.scheduler,
+                        cards: deck
+                            .cards
+                            .iter()
+                            .map(|card| JsonCard {
+                                front: card.front.clone(),
+                                back: card.back.clone(),
+                                tags: card.tags.clone(),
+                                due: card.review.due,
+                                interval: card.review.interval,
+                                ease_factor: card.review.ease_factor,
+                                repetitions: card.review.repetitions,
+                                lapses: card.review.lapses,
+                                id: Some(card.id),
+                                kind: card.kind,
+                                note_id: card.note_id,
+                                suspended: card.suspended,
+                                created: card.created,
+                                last_review: card.review.last_review,
+                                leitner_box: card.review.leitner_box,
+                                stability: card.review.stability,
+                                difficulty: card.review.difficulty,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            };
+            serde_json::to_string_pretty(&export)?
+        }
+        ExportFormat::AnkiText => {
+            // the header lines tell Anki's text importer how to read the columns
+            let mut output = String::from("#separator:tab\n#html:true\n#deck column:1\n#tags column:4\n#notetype column:5\n");
+            for row in rows {
+                let fields: Vec<String> = row.iter().map(|field| anki_field(field)).collect();
+                output.push_str(&fields.join("\t"));
+                output.push('\n');
+            }
+            output
+        }
+    };
+    Ok(output)
+}
+
+/// quotes fields that contain the delimiter, quotes or line breaks
+fn delimited_row<S: AsRef<str>>(fields: &[S], delimiter: char) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([delimiter, '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    let mut row = fields.join(&delimiter.to_string());
+    row.push('\n');
+    row
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+/// Anki reads one note per line, so line breaks become html breaks
+fn anki_field(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\t', " ")
+        .replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::CardDirection,
+        csv_import::{detect_columns, plan_import},
+    };
+
+    fn deck() -> Deck {
+        let mut deck = Deck {
+            id: 1,
+            name: String::from("Spanish"),
+            cards: Vec::new(),
+            date_last_learned: Utc::now(),
+            scheduler: Default::default(),
+            review_mode: Default::default(),
+            answer_options: Default::default(),
+            collapsed: false,
+        };
+        let tags = BTreeSet::from([String::from("verbs")]);
+        deck.add_note(String::from("hablar"), String::from("to speak"), tags, CardDirection::Both);
+        deck.add_note(String::from("{{c1::Madrid}} is in {{c2::Spain}}"), String::new(), BTreeSet::new(), CardDirection::FrontToBack);
+        deck.add_note(String::from("say \"hi\", | twice"), String::from("two\nlines"), BTreeSet::new(), CardDirection::FrontToBack);
+        deck
+    }
+
+    #[test]
+    fn delimited_rows() {
+        let cases = [
+            (vec!["a", "b"], ',', "a,b\n"),
+            (vec!["a,b", "c"], ',', "\"a,b\",c\n"),
+            (vec!["a,b", "c"], '\t', "a,b\tc\n"),
+            (vec!["say \"hi\"", ""], ',', "\"say \"\"hi\"\"\",\n"),
+            (vec!["two\nlines", "x\ry"], ',', "\"two\nlines\",\"x\ry\"\n"),
+        ];
+        for (fields, delimiter, row) in cases {
+            assert_eq!(delimited_row(&fields, delimiter), row, "{fields:?}");
+        }
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(markdown_cell("a | b\nc"), "a \\| b<br>c");
+        assert_eq!(anki_field("<b>&</b>\tx\ny"), "&lt;b&gt;&amp;&lt;/b&gt; x<br>y");
+    }
+
+    #[test]
+    fn one_row_per_note() {
+        let deck = deck();
+        assert_eq!(deck.cards.len(), 5);
+
+        let csv = export(&[&deck], ExportFormat::Csv).unwrap();
+        assert_eq!(csv.lines().filter(|line| line.starts_with("Spanish,")).count(), 3, "{csv}");
+
+        let anki = export(&[&deck], ExportFormat::AnkiText).unwrap();
+        let note_types: Vec<&str> = anki.lines().filter(|line| !line.starts_with('#')).filter_map(|line| line.split('\t').nth(4)).collect();
+        assert_eq!(note_types, ["Basic (and reversed card)", "Cloze", "Basic"]);
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let deck = deck();
+        let csv = export(&[&deck], ExportFormat::Csv).unwrap();
+        let options = detect_columns(&csv);
+        let plan = plan_import(&csv, &options, &[]);
+        assert!(plan.skipped.is_empty(), "{:?}", plan.skipped);
+        let notes: Vec<(&str, &str, Vec<&str>)> = plan
+            .cards
+            .iter()
+            .map(|(front, back, tags)| (front.as_str(), back.as_str(), tags.iter().map(String::as_str).collect()))
+            .collect();
+        assert_eq!(
+            notes,
+            [
+                ("hablar", "to speak", vec!["verbs"]),
+                ("{{c1::Madrid}} is in {{c2::Spain}}", "", vec![]),
+                ("say \"hi\", | twice", "two\nlines", vec![]),
+            ]
+        );
+    }
+}
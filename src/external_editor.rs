@@ -0,0 +1,147 @@
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    process::Command,
+};
+
+/// the fields of a card as written to the file opened in `$EDITOR`
+#[derive(Clone, PartialEq, Debug)]
+pub struct EditedNote {
+    pub front: String,
+    pub back: String,
+    pub tags: String, // separated by spaces like in the card editor
+}
+
+const FRONT_HEADER: &str = "=== front ===";
+const BACK_HEADER: &str = "=== back ===";
+const TAGS_HEADER: &str = "=== tags ===";
+
+/// the file contents for editing a card, a comment explaining the format followed by a section per field
+pub fn note_to_text(note: &EditedNote) -> String {
+    format!(
+        "# Edit the card below the section headers, lines starting with # are ignored up here.\n\
+         # Save and quit to update the card, delete everything to cancel.\n\
+         {FRONT_HEADER}\n{}\n{BACK_HEADER}\n{}\n{TAGS_HEADER}\n{}\n",
+        note.front, note.back, note.tags
+    )
+}
+
+/// reads the sections back, `None` when the file was emptied to cancel the edit
+pub fn parse_note(text: &str) -> Result<Option<EditedNote>, String> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let mut sections: [Option<Vec<&str>>; 3] = [None, None, None];
+    let mut current: Option<usize> = None;
+    for (number, line) in text.lines().enumerate() {
+        let section = match line.trim() {
+            FRONT_HEADER => Some(0),
+            BACK_HEADER => Some(1),
+            TAGS_HEADER => Some(2),
+            _ => None,
+        };
+        match (section, current) {
+            (Some(section), _) => {
+                if sections[section].is_some() {
+                    return Err(format!("line {}: {} appears twice", number + 1, line.trim()));
+                }
+                sections[section] = Some(Vec::new());
+                current = Some(section);
+            }
+            (None, Some(current)) => sections[current].get_or_insert_with(Vec::new).push(line),
+            // before the first header only comments and blank lines are allowed
+            (None, None) if line.trim().is_empty() || line.starts_with('#') => {}
+            (None, None) => return Err(format!("line {}: text before the {FRONT_HEADER} header", number + 1)),
+        }
+    }
+
+    let [front, back, tags] = sections;
+    let section_text = |lines: Option<Vec<&str>>| lines.map(|lines| lines.join("\n").trim_matches('\n').to_string());
+    let front = section_text(front).ok_or_else(|| format!("the {FRONT_HEADER} header is missing"))?;
+    if front.trim().is_empty() {
+        return Err(String::from("the front of the card is empty"));
+    }
+    Ok(Some(EditedNote { front, back: section_text(back).unwrap_or_default(), tags: section_text(tags).unwrap_or_default() }))
+}
+
+/// writes `text` to a temporary file, opens it in `$VISUAL` or `$EDITOR` (`vi` when neither is set)
+/// and returns what it contains once the editor exits. The terminal has to be handed over to the editor beforehand.
+pub fn edit(text: &str) -> io::Result<String> {
+    let command = env::var("VISUAL").or_else(|_| env::var("EDITOR")).unwrap_or_else(|_| String::from("vi"));
+    if command.trim().is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "$EDITOR is empty"));
+    }
+
+    let path = create_temp_file(text)?;
+    // the shell splits the command like git does, so editors like `code --wait` can come with arguments
+    let status = Command::new("sh").arg("-c").arg(format!("{command} \"$1\"")).arg(&command).arg(&path).status();
+    let edited = fs::read_to_string(&path);
+    fs::remove_file(&path)?;
+
+    let status = status?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{command} exited with {status}")));
+    }
+    edited
+}
+
+/// writes `text` to a new file with a random name in the temporary directory, readable only by the user
+fn create_temp_file(text: &str) -> io::Result<PathBuf> {
+    let path = env::temp_dir().join(format!("ok-card-{}.txt", rand::random::<u64>()));
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(&path)?.write_all(text.as_bytes())?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(front: &str, back: &str, tags: &str) -> EditedNote {
+        EditedNote { front: String::from(front), back: String::from(back), tags: String::from(tags) }
+    }
+
+    #[test]
+    fn round_trip() {
+        let notes = [
+            note("hola", "hello", ""),
+            note("two\nlines", "back\n\nwith a gap", "verbs spanish"),
+            note("# not a comment", "", "x"),
+        ];
+        for note in notes {
+            assert_eq!(parse_note(&note_to_text(&note)), Ok(Some(note.clone())), "{note:?}");
+        }
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(parse_note(""), Ok(None));
+        assert_eq!(parse_note(" \n\n"), Ok(None));
+        assert_eq!(parse_note("# comment\n=== front ===\nhola\n"), Ok(Some(note("hola", "", ""))));
+        assert_eq!(
+            parse_note("=== tags ===\nt\n  === back ===  \nb\n=== front ===\nf"),
+            Ok(Some(note("f", "b", "t")))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("hola\n=== front ===\nx", "line 1: text before the === front === header"),
+            ("=== back ===\nb", "the === front === header is missing"),
+            ("=== front ===\n\n=== back ===\nb", "the front of the card is empty"),
+            ("=== front ===\na\n=== front ===\nb", "line 3: === front === appears twice"),
+        ];
+        for (text, error) in cases {
+            assert_eq!(parse_note(text), Err(String::from(error)), "{text:?}");
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::{collections::BTreeSet, mem};
+
+use crate::{app::Deck, review_log::ReviewLogEntry};
+
+/// how many changes can be undone, older ones are forgotten
+const HISTORY_LIMIT: usize = 100;
+
+/// the decks a change is about to touch and the length of the review log, taken before the change so it can be undone
+pub struct Snapshot {
+    decks: DecksChange,
+    review_log_len: usize,
+}
+
+impl Snapshot {
+    /// every deck, for changes that add, remove or reorder decks
+    pub fn all(decks: &[Deck], review_log: &[ReviewLogEntry]) -> Snapshot {
+        Snapshot { decks: DecksChange::Replaced(decks.to_vec()), review_log_len: review_log.len() }
+    }
+
+    /// only the decks at `indices`, for changes that leave the other decks and the list of decks alone
+    pub fn of(decks: &[Deck], indices: impl IntoIterator<Item = usize>, review_log: &[ReviewLogEntry]) -> Snapshot {
+        let indices: BTreeSet<usize> = indices.into_iter().collect();
+        let touched = indices.into_iter().filter_map(|index| Some((index, decks.get(index)?.clone()))).collect();
+        Snapshot { decks: DecksChange::Changed(touched), review_log_len: review_log.len() }
+    }
+}
+
+/// how to bring the decks back to the other side of a change
+enum DecksChange {
+    Changed(Vec<(usize, Deck)>), // the decks that differ, when the change left the list of decks in place
+    Replaced(Vec<Deck>),         // every deck, when decks were added, removed or reordered
+}
+
+/// how to bring the review log back to the other side of a change
+enum ReviewLogChange {
+    Truncate(usize),             // the change appended the entries past this length, like grading a card
+    Append(Vec<ReviewLogEntry>), // the change removed these entries from the end
+}
+
+/// one change to the collection that can be undone or redone, only keeping what it touched
+struct Change {
+    description: String, // what the change did, like "add card hola"
+    decks: DecksChange,
+    review_log: ReviewLogChange,
+}
+
+impl Change {
+    /// the change going from `decks` and `review_log` back to `before`, `None` when nothing changed.
+    /// Outside of undo and redo the review log is only ever appended to.
+    fn between(description: String, before: Snapshot, decks: &[Deck], review_log: &[ReviewLogEntry]) -> Option<Change> {
+        let changed = |before: Vec<(usize, Deck)>| -> Vec<(usize, Deck)> {
+            before.into_iter().filter(|(index, deck)| decks.get(*index) != Some(deck)).collect()
+        };
+        let decks_change = match before.decks {
+            DecksChange::Changed(touched) => DecksChange::Changed(changed(touched)),
+            DecksChange::Replaced(other) if other.len() == decks.len() && other.iter().zip(decks).all(|(a, b)| a.id == b.id) => {
+                DecksChange::Changed(changed(other.into_iter().enumerate().collect()))
+            }
+            DecksChange::Replaced(other) => DecksChange::Replaced(other),
+        };
+
+        let unchanged = matches!(&decks_change, DecksChange::Changed(decks) if decks.is_empty());
+        if unchanged && review_log.len() == before.review_log_len {
+            return None;
+        }
+        Some(Change { description, decks: decks_change, review_log: ReviewLogChange::Truncate(before.review_log_len) })
+    }
+
+    /// brings the decks and the review log to the other side of the change and returns the change going back
+    fn apply(self, decks: &mut Vec<Deck>, review_log: &mut Vec<ReviewLogEntry>) -> Change {
+        let decks_change = match self.decks {
+            DecksChange::Changed(changed) => DecksChange::Changed(
+                changed.into_iter().map(|(index, deck)| (index, mem::replace(&mut decks[index], deck))).collect(),
+            ),
+            DecksChange::Replaced(other) => DecksChange::Replaced(mem::replace(decks, other)),
+        };
+        let review_log_change = match self.review_log {
+            ReviewLogChange::Truncate(len) => ReviewLogChange::Append(review_log.split_off(len)),
+            ReviewLogChange::Append(entries) => {
+                let len = review_log.len();
+                review_log.extend(entries);
+                ReviewLogChange::Truncate(len)
+            }
+        };
+        Change { description: self.description, decks: decks_change, review_log: review_log_change }
+    }
+}
+
+/// the changes made to the collection, to undo them one by one and redo what was undone
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Change>,
+    redo: Vec<Change>, // cleared by every new change, so only a run of undos can be redone
+}
+
+impl History {
+    /// remembers how to go back from the current `decks` and `review_log` to `before`.
+    /// Changes that didn't change anything, like saving a card without editing it, are not recorded.
+    pub fn record(&mut self, description: String, before: Snapshot, decks: &[Deck], review_log: &[ReviewLogEntry]) {
+        let Some(change) = Change::between(description, before, decks, review_log) else {
+            return;
+        };
+        self.undo.push(change);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// takes back the last change, returning what it did
+    pub fn undo(&mut self, decks: &mut Vec<Deck>, review_log: &mut Vec<ReviewLogEntry>) -> Option<String> {
+        let change = self.undo.pop()?;
+        let description = change.description.clone();
+        self.redo.push(change.apply(decks, review_log));
+        Some(description)
+    }
+
+    /// makes the last undone change again, returning what it did
+    pub fn redo(&mut self, decks: &mut Vec<Deck>, review_log: &mut Vec<ReviewLogEntry>) -> Option<String> {
+        let change = self.redo.pop()?;
+        let description = change.description.clone();
+        self.undo.push(change.apply(decks, review_log));
+        Some(description)
+    }
+
+    /// what `u` takes back
+    pub fn next_undo(&self) -> Option<&str> {
+        self.undo.last().map(|change| change.description.as_str())
+    }
+
+    /// what CTRL-R makes again
+    pub fn next_redo(&self) -> Option<&str> {
+        self.redo.last().map(|change| change.description.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::*;
+    use crate::{
+        anki_import::AnkiImport,
+        app::{App, Card, Guess},
+    };
+
+    fn deck(id: u64, fronts: &[&str]) -> Deck {
+        Deck {
+            id,
+            name: id.to_string(),
+            cards: fronts.iter().map(|front| Card::new(front.to_string(), String::new())).collect(),
+            date_last_learned: Utc::now(),
+            scheduler: Default::default(),
+            review_mode: Default::default(),
+            answer_options: Default::default(),
+            collapsed: false,
+        }
+    }
+
+    fn entry(card_id: u64) -> ReviewLogEntry {
+        ReviewLogEntry {
+            card_id,
+            deck_id: 0,
+            timestamp: Utc::now(),
+            grade: Guess::Correct,
+            previous_interval: 0,
+            new_interval: 1,
+            time_spent_ms: 0,
+        }
+    }
+
+    fn fronts(decks: &[Deck]) -> Vec<Vec<&str>> {
+        decks.iter().map(|deck| deck.cards.iter().map(|card| card.front.as_str()).collect()).collect()
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::default();
+        let mut decks = vec![deck(1, &["a"]), deck(2, &["b"])];
+        let mut review_log = Vec::new();
+
+        let before = Snapshot::of(&decks, [1], &review_log);
+        decks[1].cards[0].front = String::from("c");
+        review_log.push(entry(decks[1].cards[0].id));
+        history.record(String::from("grade"), before, &decks, &review_log);
+
+        let before = Snapshot::all(&decks, &review_log);
+        decks.push(deck(3, &["d"]));
+        history.record(String::from("add deck"), before, &decks, &review_log);
+        assert_eq!(history.next_undo(), Some("add deck"));
+
+        assert_eq!(history.undo(&mut decks, &mut review_log).as_deref(), Some("add deck"));
+        assert_eq!(fronts(&decks), [["a"], ["c"]]);
+        assert_eq!(history.undo(&mut decks, &mut review_log).as_deref(), Some("grade"));
+        assert_eq!(fronts(&decks), [["a"], ["b"]]);
+        assert!(review_log.is_empty());
+        assert_eq!(history.undo(&mut decks, &mut review_log), None);
+
+        assert_eq!(history.redo(&mut decks, &mut review_log).as_deref(), Some("grade"));
+        assert_eq!(fronts(&decks), [["a"], ["c"]]);
+        assert_eq!(review_log.len(), 1);
+        assert_eq!(history.redo(&mut decks, &mut review_log).as_deref(), Some("add deck"));
+        assert_eq!(fronts(&decks), [vec!["a"], vec!["c"], vec!["d"]]);
+        assert_eq!(history.redo(&mut decks, &mut review_log), None);
+    }
+
+    #[test]
+    fn new_changes_clear_redo() {
+        let mut history = History::default();
+        let mut decks = vec![deck(1, &["a"])];
+        let mut review_log = Vec::new();
+
+        for front in ["b", "c"] {
+            let before = Snapshot::of(&decks, [0], &review_log);
+            decks[0].cards[0].front = String::from(front);
+            history.record(format!("edit {front}"), before, &decks, &review_log);
+        }
+        history.undo(&mut decks, &mut review_log);
+        assert_eq!(history.next_redo(), Some("edit c"));
+
+        let before = Snapshot::of(&decks, [0], &review_log);
+        decks[0].cards[0].front = String::from("d");
+        history.record(String::from("edit d"), before, &decks, &review_log);
+        assert_eq!(history.next_redo(), None);
+        history.undo(&mut decks, &mut review_log);
+        assert_eq!(fronts(&decks), [["b"]]);
+    }
+
+    #[test]
+    fn undoing_an_import_restores_the_review_log() {
+        let mut app = App::new();
+        app.decks = vec![deck(1, &["a"])];
+        let card_id = app.decks[0].cards[0].id;
+        app.review_log = vec![entry(card_id), entry(card_id)];
+        let before = app.review_log.clone();
+
+        let imported = deck(2, &["b", "c"]);
+        // imported answers are older than the ones given here
+        let review_log = imported
+            .cards
+            .iter()
+            .map(|card| ReviewLogEntry { timestamp: Utc::now() - Duration::days(30), ..entry(card.id) })
+            .collect();
+        app.add_anki_import(AnkiImport { decks: vec![imported], review_log });
+        assert_eq!(app.review_log.len(), 4);
+
+        app.undo();
+        assert_eq!(app.review_log, before);
+        assert_eq!(fronts(&app.decks), [["a"]]);
+        app.redo();
+        assert_eq!(app.review_log[..2], before);
+        assert_eq!(fronts(&app.decks), [vec!["a"], vec!["b", "c"]]);
+    }
+
+    #[test]
+    fn unchanged_collection_is_not_recorded() {
+        let mut history = History::default();
+        let decks = vec![deck(1, &["a"])];
+        history.record(String::from("edit"), Snapshot::of(&decks, [0], &[]), &decks, &[]);
+        history.record(String::from("rename"), Snapshot::all(&decks, &[]), &decks, &[]);
+        assert_eq!(history.next_undo(), None);
+    }
+
+    #[test]
+    fn oldest_changes_are_forgotten() {
+        let mut history = History::default();
+        let mut decks = vec![deck(1, &["a"])];
+        let mut review_log = Vec::new();
+        for count in 0..HISTORY_LIMIT + 5 {
+            let before = Snapshot::of(&decks, [0], &review_log);
+            decks[0].cards[0].front = count.to_string();
+            history.record(count.to_string(), before, &decks, &review_log);
+        }
+        while history.undo(&mut decks, &mut review_log).is_some() {}
+        assert_eq!(fronts(&decks), [["4"]]);
+    }
+}
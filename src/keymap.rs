@@ -0,0 +1,606 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// what a key does outside of text fields, named in snake case in the key bindings file
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Back, // quits from the deck list
+    Up,
+    Down,
+    MoveUp, // moves the selected deck or card
+    MoveDown,
+    Open, // views the selected deck
+    Add,
+    Edit,
+    EditInEditor, // edits the card in `$EDITOR`
+    Duplicate,
+    Delete,
+    Rename,
+    Collapse, // hides or shows the decks nested in the selected one
+    Search, // edits the query in the card browser
+    CustomStudy,
+    Browse,
+    Import,
+    Statistics,
+    PickTheme,
+    Study,
+    CycleScheduler,
+    CycleReviewMode,
+    FilterTags,
+    Undo,
+    Redo,
+    ShowAnswer,
+    GradeIncorrect,
+    GradeCorrect,
+    GradeEasy,
+    IgnoreCase, // the options of the type the answer mode
+    IgnoreAccents,
+    IgnoreWhitespace,
+    Mark, // marks a card in the browser or toggles a tag in the tag filter
+    MarkAll,
+    MoveCards,
+    TagCards,
+    ResetCards,
+    SuspendCards,
+    ClearFilter,
+}
+
+impl Action {
+    const ALL: [Action; 39] = [
+        Action::Back,
+        Action::Up,
+        Action::Down,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Open,
+        Action::Add,
+        Action::Edit,
+        Action::EditInEditor,
+        Action::Duplicate,
+        Action::Delete,
+        Action::Rename,
+        Action::Collapse,
+        Action::Search,
+        Action::CustomStudy,
+        Action::Browse,
+        Action::Import,
+        Action::Statistics,
+        Action::PickTheme,
+        Action::Study,
+        Action::CycleScheduler,
+        Action::CycleReviewMode,
+        Action::FilterTags,
+        Action::Undo,
+        Action::Redo,
+        Action::ShowAnswer,
+        Action::GradeIncorrect,
+        Action::GradeCorrect,
+        Action::GradeEasy,
+        Action::IgnoreCase,
+        Action::IgnoreAccents,
+        Action::IgnoreWhitespace,
+        Action::Mark,
+        Action::MarkAll,
+        Action::MoveCards,
+        Action::TagCards,
+        Action::ResetCards,
+        Action::SuspendCards,
+        Action::ClearFilter,
+    ];
+
+    /// the name of the action in the key bindings file, like `grade_easy`
+    fn config_name(&self) -> String {
+        let mut name = String::new();
+        for c in format!("{self:?}").chars() {
+            if c.is_uppercase() && !name.is_empty() {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        name
+    }
+
+    /// the keys of the default preset
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Back => &["q", "esc"],
+            Action::Up => &["k", "up"],
+            Action::Down => &["j", "down"],
+            Action::MoveUp => &["K"],
+            Action::MoveDown => &["J"],
+            Action::Open => &["enter"],
+            Action::Add => &["a"],
+            Action::Edit => &["e"],
+            Action::EditInEditor => &["E"],
+            Action::Duplicate => &["y"],
+            Action::Delete => &["d"],
+            Action::Rename => &["r"],
+            Action::Collapse => &["space"],
+            Action::Search => &["/"],
+            Action::CustomStudy => &["S"],
+            Action::Browse => &["b"],
+            Action::Import => &["i"],
+            Action::Statistics => &["t"],
+            Action::PickTheme => &["T"],
+            Action::Study => &["s"],
+            Action::CycleScheduler => &["c"],
+            Action::CycleReviewMode => &["m"],
+            Action::FilterTags => &["f"],
+            Action::Undo => &["u"],
+            Action::Redo => &["ctrl-r"],
+            Action::ShowAnswer => &["enter", "space"],
+            Action::GradeIncorrect => &["h"],
+            Action::GradeCorrect => &["j"],
+            Action::GradeEasy => &["k"],
+            Action::IgnoreCase => &["c"],
+            Action::IgnoreAccents => &["a"],
+            Action::IgnoreWhitespace => &["w"],
+            Action::Mark => &["space"],
+            Action::MarkAll => &["A"],
+            Action::MoveCards => &["m"],
+            Action::TagCards => &["t"],
+            Action::ResetCards => &["r"],
+            Action::SuspendCards => &["s"],
+            Action::ClearFilter => &["c"],
+        }
+    }
+}
+
+/// the screens with keys of their own, a key can do something different in each
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KeyContext {
+    DeckList,
+    Deck,
+    Learning,
+    Browser,
+    TagFilter,
+    Statistics,
+    ThemePicker,
+}
+
+/// actions with the label of their footer hint
+type Hints = &'static [(&'static [Action], &'static str)];
+
+impl KeyContext {
+    const ALL: [KeyContext; 7] = [
+        KeyContext::DeckList,
+        KeyContext::Deck,
+        KeyContext::Learning,
+        KeyContext::Browser,
+        KeyContext::TagFilter,
+        KeyContext::Statistics,
+        KeyContext::ThemePicker,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyContext::DeckList => "the deck list",
+            KeyContext::Deck => "the deck view",
+            KeyContext::Learning => "learning mode",
+            KeyContext::Browser => "the card browser",
+            KeyContext::TagFilter => "the tag filter",
+            KeyContext::Statistics => "the statistics",
+            KeyContext::ThemePicker => "the theme picker",
+        }
+    }
+
+    /// the actions of the screen with their footer hint, in the order the hints are shown.
+    /// Actions sharing a hint like `(J/K) move` are listed together, the ones without a label aren't shown.
+    pub fn actions(&self) -> Hints {
+        match self {
+            KeyContext::DeckList => &[
+                (&[Action::Back], "quit"),
+                (&[Action::Add], "add deck"),
+                (&[Action::Rename], "rename"),
+                (&[Action::Duplicate], "duplicate"),
+                (&[Action::Delete], "delete"),
+                (&[Action::MoveDown, Action::MoveUp], "move"),
+                (&[Action::Collapse], "collapse"),
+                (&[Action::Search], "search"),
+                (&[Action::CustomStudy], "custom study"),
+                (&[Action::Browse], "browse"),
+                (&[Action::Import], "import"),
+                (&[Action::Statistics], "statistics"),
+                (&[Action::PickTheme], "theme"),
+                (&[Action::Up, Action::Down, Action::Open, Action::Undo, Action::Redo], ""),
+            ],
+            KeyContext::Deck => &[
+                (&[Action::Back], "back"),
+                (&[Action::Add], "add card"),
+                (&[Action::Edit], "edit card"),
+                (&[Action::EditInEditor], "edit in $EDITOR"),
+                (&[Action::Duplicate], "duplicate"),
+                (&[Action::Delete], "delete"),
+                (&[Action::MoveDown, Action::MoveUp], "move"),
+                (&[Action::Rename], "rename deck"),
+                (&[Action::Study], "study"),
+                (&[Action::CycleScheduler], "change scheduler"),
+                (&[Action::CycleReviewMode], "review mode"),
+                (&[Action::FilterTags], "filter tags"),
+                (&[Action::Search], "search"),
+                (&[Action::CustomStudy], "custom study"),
+                (&[Action::Browse], "browse"),
+                (&[Action::Import], "import"),
+                (&[Action::Statistics], "statistics"),
+                (&[Action::PickTheme], "theme"),
+                (&[Action::Up, Action::Down, Action::Undo, Action::Redo], ""),
+            ],
+            // the footer of learning mode shows `LEARNING_HINTS` and `ANSWER_OPTION_HINTS` depending on how the card is answered,
+            // the grades are shown under the card
+            KeyContext::Learning => &[
+                (&[Action::Back, Action::Edit, Action::EditInEditor], ""),
+                (&[Action::IgnoreCase, Action::IgnoreAccents, Action::IgnoreWhitespace], ""),
+                (&[Action::ShowAnswer, Action::GradeIncorrect, Action::GradeCorrect, Action::GradeEasy, Action::Undo, Action::Redo], ""),
+            ],
+            KeyContext::Browser => &[
+                (&[Action::Back], "back"),
+                (&[Action::Search], "query"),
+                (&[Action::Mark], "mark"),
+                (&[Action::MarkAll], "mark all"),
+                (&[Action::Edit], "edit"),
+                (&[Action::EditInEditor], "$EDITOR"),
+                (&[Action::MoveCards], "move"),
+                (&[Action::TagCards], "tag"),
+                (&[Action::Delete], "delete"),
+                (&[Action::ResetCards], "reset"),
+                (&[Action::SuspendCards], "suspend"),
+                (&[Action::Up, Action::Down, Action::Undo, Action::Redo], ""),
+            ],
+            KeyContext::TagFilter => &[
+                (&[Action::Back], "back"),
+                (&[Action::Down, Action::Up], "select"),
+                (&[Action::Mark], "toggle tag"),
+                (&[Action::ClearFilter], "clear filter"),
+            ],
+            KeyContext::Statistics => &[(&[Action::Back], "back"), (&[Action::Undo, Action::Redo], "")],
+            KeyContext::ThemePicker => &[
+                (&[Action::Back], "cancel"),
+                (&[Action::Down, Action::Up], "preview"),
+                (&[Action::Open], "keep"),
+            ],
+        }
+    }
+
+    pub fn contains(&self, action: Action) -> bool {
+        self.actions().iter().any(|(actions, _)| actions.contains(&action))
+    }
+}
+
+/// the keys of learning mode shown in the footer
+pub const LEARNING_HINTS: Hints = &[(&[Action::Back], "back"), (&[Action::Edit], "edit card"), (&[Action::EditInEditor], "$EDITOR")];
+
+/// the keys changing how typed answers are compared, shown once an answer was checked
+pub const ANSWER_OPTION_HINTS: Hints = &[
+    (&[Action::IgnoreCase], "ignore case"),
+    (&[Action::IgnoreAccents], "ignore accents"),
+    (&[Action::IgnoreWhitespace], "ignore whitespace"),
+];
+
+/// a key with the modifiers held down with it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// reads a key written like `j`, `J`, `/`, `space`, `enter`, `up` or `ctrl-r`
+    pub fn parse(text: &str) -> Result<KeyBinding, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        // a lone `-` is a key, so only a dash followed by something separates a modifier
+        while let Some((modifier, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => break,
+            };
+            rest = key;
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                _ => return Err(format!("unknown key {text}")),
+            },
+        };
+        // `shift-k` is the same key as `K`
+        if let KeyCode::Char(c) = code {
+            if modifiers.contains(KeyModifiers::SHIFT) {
+                return Ok(KeyBinding { code: KeyCode::Char(c.to_ascii_uppercase()), modifiers: modifiers - KeyModifiers::SHIFT });
+            }
+        }
+        Ok(KeyBinding { code, modifiers })
+    }
+
+    fn matches(&self, key: KeyEvent) -> bool {
+        // letters already say whether shift was held, `J` comes with or without the shift modifier depending on the terminal
+        let ignored = if matches!(key.code, KeyCode::Char(_)) { KeyModifiers::SHIFT } else { KeyModifiers::NONE };
+        self.code == key.code && self.modifiers - ignored == key.modifiers - ignored
+    }
+
+    /// the key as written in the footer, like `j`, `SPACE` or `CTRL-R`
+    pub fn name(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => String::from("SPACE"),
+            // `CTRL-R` like the other key names, without a modifier the case tells `j` and `J` apart
+            KeyCode::Char(c) if !self.modifiers.is_empty() => c.to_uppercase().to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => String::from("ENTER"),
+            KeyCode::Esc => String::from("ESC"),
+            KeyCode::Tab => String::from("TAB"),
+            KeyCode::Backspace => String::from("BACKSPACE"),
+            KeyCode::Delete => String::from("DELETE"),
+            KeyCode::Up => String::from("UP"),
+            KeyCode::Down => String::from("DOWN"),
+            KeyCode::Left => String::from("LEFT"),
+            KeyCode::Right => String::from("RIGHT"),
+            KeyCode::Home => String::from("HOME"),
+            KeyCode::End => String::from("END"),
+            KeyCode::PageUp => String::from("PAGEUP"),
+            KeyCode::PageDown => String::from("PAGEDOWN"),
+            _ => String::from("?"),
+        };
+        let mut name = String::new();
+        for (modifier, prefix) in [(KeyModifiers::CONTROL, "CTRL-"), (KeyModifiers::ALT, "ALT-"), (KeyModifiers::SHIFT, "SHIFT-")] {
+            if self.modifiers.contains(modifier) {
+                name.push_str(prefix);
+            }
+        }
+        name + &key
+    }
+}
+
+/// the sets of keys to start from, the key bindings file can change single actions on top of them
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    #[default]
+    Default,
+    Vim,    // only letters, without the arrow keys
+    Arrows, // the arrow keys to move around and numbers to grade
+}
+
+impl Preset {
+    /// the keys of `action` in the preset
+    fn keys(&self, action: Action) -> &'static [&'static str] {
+        let keys: &'static [&'static str] = match (self, action) {
+            (Preset::Vim, Action::Up) => &["k"],
+            (Preset::Vim, Action::Down) => &["j"],
+            (Preset::Vim, Action::Open) => &["l", "enter"],
+            (Preset::Vim, Action::Delete) => &["x", "d"],
+            (Preset::Arrows, Action::Back) => &["esc", "left", "q"],
+            (Preset::Arrows, Action::Up) => &["up"],
+            (Preset::Arrows, Action::Down) => &["down"],
+            (Preset::Arrows, Action::MoveUp) => &["shift-up"],
+            (Preset::Arrows, Action::MoveDown) => &["shift-down"],
+            (Preset::Arrows, Action::Open) => &["enter", "right"],
+            (Preset::Arrows, Action::GradeIncorrect) => &["1"],
+            (Preset::Arrows, Action::GradeCorrect) => &["2"],
+            (Preset::Arrows, Action::GradeEasy) => &["3"],
+            _ => &[],
+        };
+        if keys.is_empty() {
+            action.default_keys()
+        } else {
+            keys
+        }
+    }
+}
+
+/// the contents of `keys.toml` or `keys.json` in the config directory
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeymapConfig {
+    #[serde(default)]
+    preset: Preset,
+    #[serde(default)]
+    bindings: HashMap<Action, Vec<String>>, // replaces the keys of the preset for these actions
+}
+
+/// which keys trigger which action
+pub struct Keymap {
+    bindings: HashMap<Action, Vec<KeyBinding>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap::from_config(KeymapConfig { preset: Preset::Default, bindings: HashMap::new() })
+            .expect("the default key bindings are valid")
+    }
+}
+
+impl Keymap {
+    /// loads `keys.toml`, or `keys.json`, from the config directory `dir`, the default keys are used when there is neither
+    pub fn load(dir: &Path) -> io::Result<Keymap> {
+        let invalid = |path: &Path, err: String| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("could not read key bindings {}: {err}", path.display()))
+        };
+        let toml_path = dir.join("keys.toml");
+        let json_path = dir.join("keys.json");
+        let config: KeymapConfig = match fs::read_to_string(&toml_path) {
+            Ok(contents) => toml::from_str(&contents).map_err(|err| invalid(&toml_path, err.to_string()))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => match fs::read_to_string(&json_path) {
+                Ok(contents) => serde_json::from_str(&contents).map_err(|err| invalid(&json_path, err.to_string()))?,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Keymap::default()),
+                Err(err) => return Err(err),
+            },
+            Err(err) => return Err(err),
+        };
+        let path = if toml_path.exists() { toml_path } else { json_path };
+        Keymap::from_config(config).map_err(|err| invalid(&path, err))
+    }
+
+    fn from_config(config: KeymapConfig) -> Result<Keymap, String> {
+        let mut bindings = HashMap::new();
+        for action in Action::ALL {
+            let keys = match config.bindings.get(&action) {
+                Some(keys) => keys.iter().map(|key| KeyBinding::parse(key)).collect::<Result<Vec<_>, _>>()?,
+                None => config.preset.keys(action).iter().map(|key| KeyBinding::parse(key)).collect::<Result<Vec<_>, _>>()?,
+            };
+            bindings.insert(action, keys);
+        }
+        let keymap = Keymap { bindings };
+
+        // a key can only do one thing on each screen
+        for context in KeyContext::ALL {
+            let actions: Vec<Action> = Action::ALL.into_iter().filter(|action| context.contains(*action)).collect();
+            for (index, first) in actions.iter().enumerate() {
+                for second in &actions[index + 1..] {
+                    if let Some(key) = keymap.keys(*first).iter().find(|key| keymap.keys(*second).contains(key)) {
+                        return Err(format!(
+                            "{} is bound to both {} and {} in {}",
+                            key.name(),
+                            first.config_name(),
+                            second.config_name(),
+                            context.name()
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(keymap)
+    }
+
+    fn keys(&self, action: Action) -> &[KeyBinding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// the action `key` is bound to in `context`
+    pub fn action(&self, key: KeyEvent, context: KeyContext) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| context.contains(*action) && self.keys(*action).iter().any(|binding| binding.matches(key)))
+    }
+
+    /// the first key of `action` as written in the footer
+    pub fn key_name(&self, action: Action) -> String {
+        self.keys(action).first().map_or_else(|| String::from("unbound"), KeyBinding::name)
+    }
+
+    /// the footer hints for `hints`, like `(q) back/ (J/K) move`. Actions without keys are left out.
+    pub fn hints(&self, hints: &[(&[Action], &str)]) -> String {
+        hints
+            .iter()
+            .filter(|(actions, label)| !label.is_empty() && actions.iter().all(|action| !self.keys(*action).is_empty()))
+            .map(|(actions, label)| {
+                let keys: Vec<String> = actions.iter().map(|action| self.key_name(*action)).collect();
+                format!("({}) {label}", keys.join("/"))
+            })
+            .collect::<Vec<_>>()
+            .join("/ ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(code: KeyCode, modifiers: KeyModifiers) -> KeyBinding {
+        KeyBinding { code, modifiers }
+    }
+
+    fn config(preset: Preset, bindings: &[(Action, &[&str])]) -> KeymapConfig {
+        let bindings = bindings.iter().map(|(action, keys)| (*action, keys.iter().map(|key| key.to_string()).collect())).collect();
+        KeymapConfig { preset, bindings }
+    }
+
+    #[test]
+    fn parsing_keys() {
+        let cases = [
+            ("j", binding(KeyCode::Char('j'), KeyModifiers::NONE)),
+            ("J", binding(KeyCode::Char('J'), KeyModifiers::NONE)),
+            ("shift-j", binding(KeyCode::Char('J'), KeyModifiers::NONE)),
+            ("-", binding(KeyCode::Char('-'), KeyModifiers::NONE)),
+            ("ctrl--", binding(KeyCode::Char('-'), KeyModifiers::CONTROL)),
+            ("space", binding(KeyCode::Char(' '), KeyModifiers::NONE)),
+            ("Enter", binding(KeyCode::Enter, KeyModifiers::NONE)),
+            ("ctrl-r", binding(KeyCode::Char('r'), KeyModifiers::CONTROL)),
+            ("ctrl-alt-x", binding(KeyCode::Char('x'), KeyModifiers::CONTROL | KeyModifiers::ALT)),
+            ("shift-down", binding(KeyCode::Down, KeyModifiers::SHIFT)),
+        ];
+        for (text, key) in cases {
+            assert_eq!(KeyBinding::parse(text), Ok(key), "{text}");
+        }
+        for text in ["", "jk", "hyper-j", "ctrl-", "f13"] {
+            assert!(KeyBinding::parse(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn key_names() {
+        let cases = [("j", "j"), ("space", "SPACE"), ("ctrl-r", "CTRL-R"), ("shift-up", "SHIFT-UP"), ("shift-k", "K")];
+        for (text, name) in cases {
+            assert_eq!(KeyBinding::parse(text).map(|key| key.name()), Ok(String::from(name)), "{text}");
+        }
+    }
+
+    #[test]
+    fn matching_keys() {
+        let upper = KeyBinding::parse("J").unwrap();
+        assert!(upper.matches(KeyEvent::new(KeyCode::Char('J'), KeyModifiers::SHIFT)));
+        assert!(upper.matches(KeyEvent::new(KeyCode::Char('J'), KeyModifiers::NONE)));
+        assert!(!upper.matches(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE)));
+        let redo = KeyBinding::parse("ctrl-r").unwrap();
+        assert!(!redo.matches(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE)));
+        let move_down = KeyBinding::parse("shift-down").unwrap();
+        assert!(!move_down.matches(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn presets_have_no_conflicts() {
+        for preset in [Preset::Default, Preset::Vim, Preset::Arrows] {
+            if let Err(err) = Keymap::from_config(config(preset, &[])) {
+                panic!("{preset:?}: {err}");
+            }
+        }
+    }
+
+    #[test]
+    fn presets_and_bindings() {
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        let vim = Keymap::from_config(config(Preset::Vim, &[])).unwrap();
+        assert_eq!(vim.action(key('x'), KeyContext::Deck), Some(Action::Delete));
+        assert_eq!(vim.action(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE), KeyContext::Deck), None);
+
+        let arrows = Keymap::from_config(config(Preset::Arrows, &[])).unwrap();
+        assert_eq!(arrows.action(key('3'), KeyContext::Learning), Some(Action::GradeEasy));
+        assert_eq!(arrows.action(key('k'), KeyContext::Learning), None);
+
+        let custom = Keymap::from_config(config(Preset::Default, &[(Action::Redo, &["ctrl-y", "U"])])).unwrap();
+        assert_eq!(custom.action(key('U'), KeyContext::DeckList), Some(Action::Redo));
+        assert_eq!(custom.key_name(Action::Redo), "CTRL-Y");
+        assert_eq!(custom.hints(&[(&[Action::Back], "back"), (&[Action::MoveDown, Action::MoveUp], "move")]), "(q) back/ (J/K) move");
+
+        let unbound = Keymap::from_config(config(Preset::Default, &[(Action::Statistics, &[])])).unwrap();
+        assert_eq!(unbound.key_name(Action::Statistics), "unbound");
+        assert_eq!(unbound.hints(&[(&[Action::Statistics], "statistics")]), "");
+    }
+
+    #[test]
+    fn conflicts() {
+        let result = Keymap::from_config(config(Preset::Default, &[(Action::Add, &["d"])]));
+        assert_eq!(result.err(), Some(String::from("d is bound to both add and delete in the deck list")));
+        // the same key can do different things on different screens
+        assert!(Keymap::from_config(config(Preset::Default, &[(Action::Study, &["t"]), (Action::Statistics, &["x"])])).is_ok());
+        assert!(Keymap::from_config(config(Preset::Default, &[(Action::Add, &["nope"])])).is_err());
+    }
+}
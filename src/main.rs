@@ -1,8 +1,9 @@
-use std::{error::Error, io};
+use std::{env, error::Error, io, process};
 
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode,
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event, KeyCode,
+        KeyModifiers,
     },
     execute,
     terminal::{
@@ -15,23 +16,61 @@ use ratatui::{
     Terminal,
 };
 
+mod anki_import;
 mod app;
+mod browser;
+mod cli;
+mod cloze;
+mod csv_import;
+mod deck_tree;
+mod export;
+mod external_editor;
+mod history;
+mod keymap;
+mod multiple_choice;
+mod query;
+mod review_log;
+mod scheduler;
+mod search;
+mod stats;
+mod storage;
+mod text_input;
+mod theme;
+mod typed_answer;
 mod ui;
 use crate::{
-    app::{App, CurrentScreen, CardFace},
+    app::{App, CurrentScreen, CardFace, DeleteTarget, EditorField, ExternalEdit, Guess},
+    browser::{Browser, BulkAction},
+    export::ExportFormat,
+    keymap::{Action, Keymap},
     ui::ui,
 };
 
 fn main() -> Result<(), Box<dyn Error>> {
+    // load the collection before touching the terminal so errors are printed normally
+    let mut app = App::load(storage::default_collection_path())?;
+
+    // subcommands work on the collection without starting the terminal interface
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(err) = cli::run(&mut app, &args) {
+            eprintln!("ok-card: {err}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    app.keymap = Keymap::load(&storage::config_dir())?;
+    app.load_themes(storage::config_dir());
+
     // setup terminal
     enable_raw_mode()?;
     let mut stderr = io::stderr(); // This is a special case. Normally using stdout is fine
-    execute!(stderr, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stderr, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stderr);
     let mut terminal = Terminal::new(backend)?;
 
-    // create app and run it
-    let mut app = App::new();
+    // run the app
     let res = run_app(&mut terminal, &mut app);
 
 
@@ -40,61 +79,138 @@ fn main() -> Result<(), Box<dyn Error>> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
-    if let Ok(do_print) = res {
-        if do_print {
-            app.print_json()?;
+    app.save()?;
+
+    match res {
+        Ok(Some(format)) => {
+            let decks = app.decks_to_export();
+            let path = export::export_to_new_file(&export::default_export_path(&decks, format), &decks, format)?;
+            println!("Exported {} decks as {} to {}", decks.len(), format.name(), path.display());
         }
-    } else if let Err(err) = res {
-        println!("{err:?}");
+        Ok(None) => {}
+        Err(err) => println!("{err:?}"),
     }
 
     Ok(())
 }
 
-fn run_app<B: Backend>(
+/// hands the terminal over to `$EDITOR` to edit `text`, leaving raw mode and the alternate screen until it exits
+fn run_external_editor<B: Backend + io::Write>(terminal: &mut Terminal<B>, text: &str) -> io::Result<io::Result<String>> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste)?;
+    terminal.show_cursor()?;
+
+    let edited = external_editor::edit(text);
+
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
+    // the editor drew over the screen, so everything is drawn again
+    terminal.clear()?;
+    Ok(edited)
+}
+
+fn run_app<B: Backend + io::Write>(
     terminal: &mut Terminal<B>,
     app: &mut App,
-) -> io::Result<bool> {
+) -> io::Result<Option<ExportFormat>> {
     loop {
+        if app.external_edit.is_some() {
+            let edited = run_external_editor(terminal, &app.external_editor_text())?;
+            app.finish_external_edit(edited);
+            app.save()?;
+        }
         terminal.draw(|f| ui(f, app))?;
 
-        if let Event::Key(key) = event::read()? {
+        let event = event::read()?;
+        // pasted text arrives in one piece instead of as keys, so its newlines don't submit the editor
+        if let Event::Paste(text) = &event {
+            app.paste(text);
+            continue;
+        }
+        if let Event::Key(key) = event {
             if key.kind == event::KeyEventKind::Release {
                 // Skip events that are not KeyEventKind::Press
                 continue;
             }
+            // the footer shows the outcome of the last key until the next one is pressed
+            app.status_message = None;
+            // the keys bound in the keymap, on the screens where keys aren't typed in as text
+            let action = app.key_context().and_then(|context| app.keymap.action(key, context));
+            match action {
+                Some(Action::Undo) => {
+                    app.undo();
+                    app.save()?;
+                    continue;
+                }
+                Some(Action::Redo) => {
+                    app.redo();
+                    app.save()?;
+                    continue;
+                }
+                _ => {}
+            }
             match app.current_screen {
-                CurrentScreen::Main => match key.code {
-                    KeyCode::Char('e') => {
-                    }
-                    KeyCode::Char('a') => {
-                        app.current_screen = CurrentScreen::AddingDeck;
-                        app.adding_deck = true;
+                CurrentScreen::Main => match action {
+                    Some(Action::Add) => {
+                        app.start_adding_deck();
                     }
-                    KeyCode::Char('q') => {
+                    Some(Action::Back) => {
                         app.current_screen = CurrentScreen::Exiting;
                     }
-                    KeyCode::Char('k') => {
-                        if let Some(index) = app.selected_index {
-                            if index > 0 {
-                                app.selected_index = Some(index - 1);
-                            }
-                        }
+                    Some(Action::Statistics) => {
+                        app.open_statistics();
+                    }
+                    Some(Action::PickTheme) => {
+                        app.open_theme_picker();
+                    }
+                    Some(Action::Import) => {
+                        app.start_import();
                     }
-                    KeyCode::Char('j') => {
+                    Some(Action::Up) => {
+                        app.select_previous_deck();
+                    }
+                    Some(Action::Down) => {
+                        app.select_next_deck();
+                    }
+                    Some(Action::MoveUp) => {
+                        app.move_deck(-1);
+                        app.save()?;
+                    }
+                    Some(Action::MoveDown) => {
+                        app.move_deck(1);
+                        app.save()?;
+                    }
+                    Some(Action::Rename) => {
+                        app.start_renaming_deck();
+                    }
+                    Some(Action::Collapse) => {
+                        app.toggle_collapsed();
+                        app.save()?;
+                    }
+                    Some(Action::Search) => {
+                        app.start_search();
+                    }
+                    Some(Action::CustomStudy) => {
+                        app.start_custom_study_query();
+                    }
+                    Some(Action::Browse) => {
+                        app.open_browser();
+                    }
+                    Some(Action::Duplicate) => {
+                        app.duplicate_deck();
+                        app.save()?;
+                    }
+                    Some(Action::Delete) => {
                         if let Some(index) = app.selected_index {
-                            if index < app.decks.len() - 1 {
-                                app.selected_index = Some(index + 1);
-                            }
-                        } else {
-                            app.selected_index = Some(0);
+                            app.request_delete(DeleteTarget::Deck(index));
                         }
                     }
-                    KeyCode::Enter => {
+                    Some(Action::Open) => {
                         if let Some(_index) = app.selected_index {
                             app.display_decks = false;
                             app.current_screen = CurrentScreen::ViewingDeck;
@@ -102,41 +218,129 @@ fn run_app<B: Backend>(
                     }
                     _ => {}
                 },
-                CurrentScreen::ViewingDeck => match key.code {
-                    KeyCode::Char('q') => {
+                CurrentScreen::ViewingDeck => match action {
+                    Some(Action::Back) => {
                         app.display_decks = true;
                         app.current_screen = CurrentScreen::Main;
                         app.selected_card_index = None;
+                        app.tag_filter.clear();
                     }
-                    KeyCode::Char('k') => {
-                        if let Some(index) = app.selected_card_index {
-                            if index > 0 {
-                                app.selected_card_index = Some(index - 1);
-                            }
+                    Some(Action::FilterTags) => {
+                        app.open_tag_filter();
+                    }
+                    Some(Action::Search) => {
+                        app.start_search();
+                    }
+                    Some(Action::CustomStudy) => {
+                        app.start_custom_study_query();
+                    }
+                    Some(Action::Browse) => {
+                        app.open_browser();
+                    }
+                    Some(Action::Up) => {
+                        app.select_previous_card();
+                    }
+                    Some(Action::Down) => {
+                        app.select_next_card();
+                    }
+                    Some(Action::MoveUp) => {
+                        app.move_card(-1);
+                        app.save()?;
+                    }
+                    Some(Action::MoveDown) => {
+                        app.move_card(1);
+                        app.save()?;
+                    }
+                    Some(Action::Rename) => {
+                        app.start_renaming_deck();
+                    }
+                    Some(Action::Duplicate) => {
+                        app.duplicate_card();
+                        app.save()?;
+                    }
+                    Some(Action::Delete) => {
+                        if let Some(card_index) = app.selected_card_index {
+                            app.request_delete(DeleteTarget::Card(card_index));
                         }
                     }
-                    KeyCode::Char('j') => {
-                        if let Some(index) = app.selected_card_index {
-                            if index < app.decks[app.selected_index.unwrap_or_default()].cards.len() - 1 {
-                                app.selected_card_index = Some(index + 1);
-                            }
-                        } else {
-                            app.selected_card_index = Some(0);
+                    Some(Action::Add) => {
+                        app.start_adding_card();
+                    }
+                    Some(Action::Edit) => {
+                        if let (Some(deck_index), Some(card_index)) = (app.selected_index, app.selected_card_index) {
+                            app.start_editing_card(deck_index, card_index);
                         }
                     }
-                    KeyCode::Char('a') => {
-                        app.card_currently_editing = Some(CardFace::CardFront);
-                        app.current_screen = CurrentScreen::EditingCard;
+                    Some(Action::EditInEditor) => {
+                        if let (Some(deck_index), Some(card_index)) = (app.selected_index, app.selected_card_index) {
+                            app.start_external_edit(deck_index, card_index);
+                        }
+                    }
+                    Some(Action::Statistics) => {
+                        app.open_statistics();
+                    }
+                    Some(Action::PickTheme) => {
+                        app.open_theme_picker();
+                    }
+                    Some(Action::Import) => {
+                        app.start_import();
                     }
-                    KeyCode::Char('s') => {
+                    Some(Action::CycleScheduler) => {
+                        app.cycle_scheduler();
+                        app.save()?;
+                    }
+                    Some(Action::CycleReviewMode) => {
+                        app.cycle_review_mode();
+                        app.save()?;
+                    }
+                    Some(Action::Study) => {
                         app.selected_card_index = None;
                         app.next_card_to_learn();
                         app.current_screen = CurrentScreen::LearningMode;
                     }
                     _ => {}
                 },
-                CurrentScreen::LearningMode => match key.code {
-                    KeyCode::Char(' ') | KeyCode::Enter => {
+                CurrentScreen::LearningMode if app.typing_answer() => match key.code {
+                    KeyCode::Enter => {
+                        app.face_showing = Some(CardFace::CardBack);
+                    }
+                    KeyCode::Esc => {
+                        app.stop_learning();
+                    }
+                    _ => {
+                        app.answer_input.handle_key(key);
+                    }
+                },
+                // picking a choice and accepting the outcome of a typed or picked answer aren't remapped
+                CurrentScreen::LearningMode => match (key.code, action) {
+                    (KeyCode::Char(value), _) if app.choosing_answer() && value.is_ascii_digit() => {
+                        app.pick_choice(value as usize - '0' as usize);
+                    }
+                    (KeyCode::Enter, _) if app.choice_guess().is_some() => {
+                        if let Some(guess) = app.choice_guess() {
+                            app.answer_card(guess);
+                            app.save()?;
+                        }
+                    }
+                    (KeyCode::Enter, _) if app.answer_check().is_some() => {
+                        if let Some(check) = app.answer_check() {
+                            app.answer_card(check.suggested);
+                            app.save()?;
+                        }
+                    }
+                    (_, Some(Action::IgnoreCase)) if app.answer_check().is_some() => {
+                        app.update_answer_options(|options| options.ignore_case = !options.ignore_case);
+                        app.save()?;
+                    }
+                    (_, Some(Action::IgnoreAccents)) if app.answer_check().is_some() => {
+                        app.update_answer_options(|options| options.ignore_accents = !options.ignore_accents);
+                        app.save()?;
+                    }
+                    (_, Some(Action::IgnoreWhitespace)) if app.answer_check().is_some() => {
+                        app.update_answer_options(|options| options.ignore_whitespace = !options.ignore_whitespace);
+                        app.save()?;
+                    }
+                    (_, Some(Action::ShowAnswer)) if !app.choosing_answer() => {
                         if let Some(face_showing) = &app.face_showing {
                             match face_showing {
                                 CardFace::CardFront => {
@@ -147,106 +351,301 @@ fn run_app<B: Backend>(
                             }
                         }
                     }
-                    KeyCode::Char('h') => {
-                        app.next_card_to_learn();
+                    (_, Some(Action::GradeIncorrect)) if app.answer_revealed() => {
+                        app.answer_card(Guess::Incorrect);
+                        app.save()?;
                     }
-                    KeyCode::Char('k') => {
-                        app.next_card_to_learn();
+                    (_, Some(Action::GradeEasy)) if app.answer_revealed() => {
+                        app.answer_card(Guess::Easy);
+                        app.save()?;
                     }
-                    KeyCode::Char('j') => {
-                        app.next_card_to_learn();
+                    (_, Some(Action::GradeCorrect)) if app.answer_revealed() => {
+                        app.answer_card(Guess::Correct);
+                        app.save()?;
+                    }
+                    (_, Some(Action::Edit)) => {
+                        if let Some((deck_index, card_index)) = app.card_currently_learning {
+                            app.start_editing_card(deck_index, card_index);
+                        }
                     }
-                    KeyCode::Char('e') => {
-                        app.current_screen = CurrentScreen::EditingCard;
+                    (_, Some(Action::EditInEditor)) => {
+                        if let Some((deck_index, card_index)) = app.card_currently_learning {
+                            app.start_external_edit(deck_index, card_index);
+                        }
                     }
-                    KeyCode::Char('q') => {
-                        app.current_screen = CurrentScreen::ViewingDeck;
-                        app.selected_card_index = None;
+                    (_, Some(Action::Back)) => {
+                        app.stop_learning();
+                    }
+                    _ => {}
+                },
+                CurrentScreen::FilteringTags => match (key.code, action) {
+                    (_, Some(Action::Up)) => {
+                        app.select_previous_tag();
+                    }
+                    (_, Some(Action::Down)) => {
+                        app.select_next_tag();
+                    }
+                    (_, Some(Action::Mark)) => {
+                        app.toggle_tag_filter();
+                    }
+                    (_, Some(Action::ClearFilter)) => {
+                        app.clear_tag_filter();
+                    }
+                    (KeyCode::Enter, _) | (_, Some(Action::Back)) => {
+                        app.close_tag_filter();
+                    }
+                    _ => {}
+                },
+                CurrentScreen::Searching => match key.code {
+                    KeyCode::Up => {
+                        app.select_previous_search_result();
+                    }
+                    KeyCode::Down => {
+                        app.select_next_search_result();
+                    }
+                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.select_previous_search_result();
+                    }
+                    KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.select_next_search_result();
+                    }
+                    KeyCode::Enter => {
+                        app.open_search_result();
+                    }
+                    KeyCode::Esc => {
+                        app.cancel_search();
+                    }
+                    _ => {
+                        if app.search_input.handle_key(key) {
+                            app.update_search();
+                        }
+                    }
+                },
+                CurrentScreen::EnteringQuery => match key.code {
+                    KeyCode::Enter => {
+                        app.start_custom_study();
+                    }
+                    KeyCode::Esc => {
+                        app.cancel_custom_study_query();
+                    }
+                    _ => {
+                        if app.query_input.handle_key(key) {
+                            app.update_query();
+                        }
+                    }
+                },
+                CurrentScreen::Browsing if app.browser_action().is_some() => match key.code {
+                    KeyCode::Enter => {
+                        app.apply_browser_action();
+                        app.save()?;
+                    }
+                    KeyCode::Esc => {
+                        app.cancel_browser_action();
+                    }
+                    _ => {
+                        if let Some(browser) = &mut app.browser {
+                            browser.action_input.handle_key(key);
+                        }
+                    }
+                },
+                CurrentScreen::Browsing if app.editing_browser_query() => match key.code {
+                    KeyCode::Enter | KeyCode::Esc => {
+                        if let Some(browser) = &mut app.browser {
+                            browser.editing_query = false;
+                        }
+                    }
+                    _ => {
+                        if app.browser.as_mut().is_some_and(|browser| browser.query_input.handle_key(key)) {
+                            app.refresh_browser();
+                        }
+                    }
+                },
+                CurrentScreen::Browsing => match (key.code, action) {
+                    (_, Some(Action::Back)) => {
+                        app.close_browser();
+                    }
+                    (_, Some(Action::Search)) => {
+                        if let Some(browser) = &mut app.browser {
+                            browser.editing_query = true;
+                        }
+                    }
+                    (_, Some(Action::Up)) => {
+                        app.select_previous_browser_row();
+                    }
+                    (_, Some(Action::Down)) => {
+                        app.select_next_browser_row();
+                    }
+                    (_, Some(Action::Mark)) => {
+                        app.toggle_browser_mark();
+                    }
+                    (_, Some(Action::MarkAll)) => {
+                        app.toggle_browser_mark_all();
+                    }
+                    (_, Some(Action::Edit)) => {
+                        app.edit_browser_card();
+                    }
+                    (_, Some(Action::EditInEditor)) => {
+                        if let Some((deck_index, card_index)) = app.browser.as_ref().and_then(Browser::selected_row) {
+                            app.start_external_edit(deck_index, card_index);
+                        }
+                    }
+                    (_, Some(Action::MoveCards)) => {
+                        app.start_browser_action(BulkAction::Move);
+                    }
+                    (_, Some(Action::TagCards)) => {
+                        app.start_browser_action(BulkAction::Tag);
+                    }
+                    (_, Some(Action::Delete)) if !app.browser_targets().is_empty() => {
+                        app.request_delete(DeleteTarget::BrowserCards);
+                    }
+                    (_, Some(Action::ResetCards)) => {
+                        app.reset_browser_cards();
+                        app.save()?;
+                    }
+                    (_, Some(Action::SuspendCards)) => {
+                        app.toggle_browser_cards_suspended();
+                        app.save()?;
+                    }
+                    // the columns are sorted by their number
+                    (KeyCode::Char(value @ '1'..='9'), _) => {
+                        app.sort_browser(value as usize - '0' as usize);
+                    }
+                    _ => {}
+                },
+                CurrentScreen::Statistics => {
+                    if action == Some(Action::Back) {
+                        app.close_statistics();
+                    }
+                }
+                CurrentScreen::PickingTheme => match action {
+                    Some(Action::Up) => {
+                        app.select_previous_theme();
+                    }
+                    Some(Action::Down) => {
+                        app.select_next_theme();
+                    }
+                    Some(Action::Open) => {
+                        app.keep_theme();
+                    }
+                    Some(Action::Back) => {
+                        app.cancel_theme_picker();
                     }
                     _ => {}
                 },
                 CurrentScreen::Exiting => match key.code {
-                    KeyCode::Char('y') => {
-                        return Ok(true);
+                    KeyCode::Char(value @ '1'..='5') => {
+                        let choice = value as usize - '1' as usize;
+                        return Ok(Some(ExportFormat::ALL[choice]));
+                    }
+                    KeyCode::Char('a') => {
+                        app.export_all_decks = !app.export_all_decks;
                     }
                     KeyCode::Char('n') | KeyCode::Char('q') => {
-                        return Ok(false);
+                        return Ok(None);
+                    }
+                    KeyCode::Esc => {
+                        app.current_screen = CurrentScreen::Main;
                     }
                     _ => {}
                 },
                 CurrentScreen::AddingDeck => match key.code {
-                    KeyCode::Enter => {
-                        if !app.name_input.is_empty() {
-                            app.add_deck(app.name_input.clone());
-                            app.name_input = String::new();
-                            app.adding_deck = false;
-                            app.current_screen = CurrentScreen::Main;
+                    // a name that is already taken keeps the popup open, the footer says why
+                    KeyCode::Enter if !app.name_input.is_empty() => {
+                        if app.save_deck_name() {
+                            app.save()?;
+                            app.stop_editing_deck_name();
                         }
                     }
-                    KeyCode::Backspace => {
-                        app.name_input.pop();
+                    KeyCode::Esc => {
+                        app.stop_editing_deck_name();
+                    }
+                    _ => {
+                        app.name_input.handle_key(key);
+                    }
+                },
+                CurrentScreen::EnteringImportPath => match key.code {
+                    KeyCode::Enter if !app.path_input.is_empty() => {
+                        app.open_import();
                     }
                     KeyCode::Esc => {
-                        app.adding_deck = false;
-                        app.current_screen = CurrentScreen::Main;
+                        app.cancel_import();
                     }
-                    KeyCode::Char(value) => {
-                        app.name_input.push(value);
+                    _ => {
+                        app.path_input.handle_key(key);
                     }
-                    _ => {}
                 },
-                CurrentScreen::EditingCard => match key.code {
+                CurrentScreen::ImportPreview => match key.code {
                     KeyCode::Enter => {
-                        if let Some(editing) = &app.card_currently_editing {
-                            match editing {
-                                CardFace::CardFront => {
-                                    app.card_currently_editing = Some(CardFace::CardBack);
-                                }
-                                CardFace::CardBack => {
-                                    app.add_card();
-                                    app.front_input = String::new();
-                                    app.back_input = String::new();
-                                    app.current_screen =
-                                        CurrentScreen::ViewingDeck;
-                                    app.card_currently_editing = None;
-                                }
-                            }
-                        }
+                        app.finish_import();
+                        app.save()?;
+                    }
+                    KeyCode::Char('d') => {
+                        app.update_import(|import, _| import.cycle_delimiter());
+                    }
+                    KeyCode::Char('h') => {
+                        app.update_import(|import, _| import.options.has_header = !import.options.has_header);
+                    }
+                    KeyCode::Char('f') => {
+                        app.update_import(|import, _| import.cycle_front_column());
+                    }
+                    KeyCode::Char('b') => {
+                        app.update_import(|import, _| import.cycle_back_column());
+                    }
+                    KeyCode::Char('t') => {
+                        app.update_import(|import, _| import.cycle_tags_column());
                     }
-                    KeyCode::Backspace => {
+                    KeyCode::Char('n') => {
+                        app.update_import(|import, decks| import.cycle_target(decks));
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => {
+                        app.cancel_import();
+                    }
+                    _ => {}
+                },
+                CurrentScreen::ConfirmingDelete => match key.code {
+                    KeyCode::Char('y') => {
+                        app.confirm_delete();
+                        app.save()?;
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => {
+                        app.cancel_delete();
+                    }
+                    _ => {}
+                },
+                CurrentScreen::EditingCard => match key.code {
+                    // ALT-ENTER and SHIFT-ENTER start a new line in the front and back
+                    KeyCode::Enter if key.modifiers.is_empty() => {
                         if let Some(editing) = &app.card_currently_editing {
                             match editing {
-                                CardFace::CardFront => {
-                                    app.front_input.pop();
+                                EditorField::Front => {
+                                    app.card_currently_editing = Some(EditorField::Back);
                                 }
-                                CardFace::CardBack => {
-                                    app.back_input.pop();
+                                EditorField::Back | EditorField::Tags => {
+                                    app.save_edited_card();
+                                    app.save()?;
+                                    app.stop_editing_card();
                                 }
                             }
                         }
                     }
                     KeyCode::Esc => {
-                        app.current_screen = CurrentScreen::ViewingDeck;
-                        app.card_currently_editing = None;
+                        app.stop_editing_card();
                     }
                     KeyCode::Tab => {
                         app.toggle_card_currently_editing();
                     }
-                    KeyCode::Char(value) => {
-                        if let Some(editing) = &app.card_currently_editing {
-                            match editing {
-                                CardFace::CardFront => {
-                                    app.front_input.push(value);
-                                }
-                                CardFace::CardBack => {
-                                    app.back_input.push(value);
-                                }
-                            }
+                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.cycle_card_direction();
+                    }
+                    KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.external_edit = Some(ExternalEdit::Fields);
+                    }
+                    _ => {
+                        if let Some(input) = app.active_input_mut() {
+                            input.handle_key(key);
                         }
                     }
-                    _ => {}
                 },
-                _ => {}
             }
         }
     }
@@ -0,0 +1,143 @@
+use std::cmp::Reverse;
+
+use rand::seq::SliceRandom;
+
+use crate::app::{Card, Deck, Guess};
+
+/// how many wrong answers are shown next to the right one when the deck has enough cards
+pub const DISTRACTOR_COUNT: usize = 4;
+
+/// the fewest wrong answers worth offering, for at least three choices
+const MIN_DISTRACTORS: usize = 2;
+
+/// the answers offered for the card being learned in the multiple choice mode
+#[derive(Clone, PartialEq, Debug)]
+pub struct MultipleChoice {
+    pub choices: Vec<String>,
+    pub correct: usize, // the index of the right answer in `choices`
+    pub picked: Option<usize>, // the choice the user made, `None` until one is picked
+}
+
+impl MultipleChoice {
+    /// the right answer of the card at `card_index` shuffled among the answers of other cards of `deck`.
+    /// `None` when fewer than two other cards have a different answer to offer.
+    pub fn new(deck: &Deck, card_index: usize) -> Option<MultipleChoice> {
+        let card = deck.cards.get(card_index)?;
+        let answer = card.expected_answer();
+        let distractors = distractors(deck, card, &answer);
+        if distractors.len() < MIN_DISTRACTORS {
+            return None;
+        }
+
+        let mut choices = distractors;
+        choices.push(answer.clone());
+        choices.shuffle(&mut rand::thread_rng());
+        let correct = choices.iter().position(|choice| *choice == answer)?;
+        Some(MultipleChoice { choices, correct, picked: None })
+    }
+
+    /// the grade the picked choice maps onto, picking the wrong answer is an incorrect guess
+    pub fn guess(&self) -> Option<Guess> {
+        self.picked.map(|picked| if picked == self.correct { Guess::Correct } else { Guess::Incorrect })
+    }
+}
+
+/// wrong answers taken from the other cards of the deck.
+/// Answers sharing tags with the card come first, then the ones closest in length so the right one doesn't stand out,
+/// and the distractors are picked at random from twice as many of the best candidates so they vary between reviews.
+fn distractors(deck: &Deck, card: &Card, answer: &str) -> Vec<String> {
+    let mut candidates: Vec<(usize, usize, String)> = Vec::new();
+    for other in &deck.cards {
+        let same_note = other.note_id.is_some() && other.note_id == card.note_id;
+        if other.id == card.id || same_note {
+            continue;
+        }
+        let other_answer = other.expected_answer();
+        if other_answer.trim().is_empty()
+            || same_answer(&other_answer, answer)
+            || candidates.iter().any(|(_, _, candidate)| same_answer(candidate, &other_answer))
+        {
+            continue;
+        }
+        let shared_tags = other.tags.intersection(&card.tags).count();
+        let length_difference = other_answer.chars().count().abs_diff(answer.chars().count());
+        candidates.push((shared_tags, length_difference, other_answer));
+    }
+
+    let mut rng = rand::thread_rng();
+    candidates.shuffle(&mut rng);
+    candidates.sort_by_key(|(shared_tags, length_difference, _)| (Reverse(*shared_tags), *length_difference));
+    candidates.truncate(DISTRACTOR_COUNT * 2);
+    candidates.shuffle(&mut rng);
+    candidates.into_iter().take(DISTRACTOR_COUNT).map(|(_, _, answer)| answer).collect()
+}
+
+/// answers that only differ in case or surrounding spaces look the same as choices
+fn same_answer(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::app::CardDirection;
+
+    fn deck(notes: &[(&str, &str)]) -> Deck {
+        let mut deck = Deck {
+            id: 1,
+            name: String::from("Spanish"),
+            cards: Vec::new(),
+            date_last_learned: Utc::now(),
+            scheduler: Default::default(),
+            review_mode: Default::default(),
+            answer_options: Default::default(),
+            collapsed: false,
+        };
+        for (front, back) in notes {
+            deck.add_note(front.to_string(), back.to_string(), BTreeSet::new(), CardDirection::FrontToBack);
+        }
+        deck
+    }
+
+    #[test]
+    fn too_few_distractors() {
+        let cases: [&[(&str, &str)]; 4] = [
+            &[("hola", "hello")],
+            &[("hola", "hello"), ("adios", "bye")],
+            // duplicate, blank and equal answers don't count
+            &[("hola", "hello"), ("adios", "bye"), ("chao", "bye"), ("nada", " "), ("buenas", "Hello ")],
+            // the cards of the same note don't count either
+            &[("{{c1::hola}} {{c2::amigo}} {{c3::bueno}}", ""), ("adios", "bye")],
+        ];
+        for notes in cases {
+            assert_eq!(MultipleChoice::new(&deck(notes), 0), None, "{notes:?}");
+        }
+    }
+
+    #[test]
+    fn choices() {
+        let deck = deck(&[("hola", "hello"), ("adios", "bye"), ("chao", "bye"), ("gato", "cat"), ("perro", ""), ("uno", "one")]);
+        for _ in 0..20 {
+            let choice = MultipleChoice::new(&deck, 0).unwrap();
+            assert_eq!(choice.choices[choice.correct], "hello");
+            let unique: BTreeSet<&String> = choice.choices.iter().collect();
+            assert_eq!(unique.len(), 4, "{:?}", choice.choices);
+            assert_eq!(BTreeSet::from_iter(choice.choices.iter().map(String::as_str)), BTreeSet::from(["hello", "bye", "cat", "one"]));
+        }
+        assert_eq!(MultipleChoice::new(&deck, 99), None);
+    }
+
+    #[test]
+    fn guesses() {
+        let mut choice = MultipleChoice { choices: vec![String::from("a"), String::from("b"), String::from("c")], correct: 1, picked: None };
+        assert_eq!(choice.guess(), None);
+        choice.picked = Some(1);
+        assert_eq!(choice.guess(), Some(Guess::Correct));
+        choice.picked = Some(2);
+        assert_eq!(choice.guess(), Some(Guess::Incorrect));
+    }
+}
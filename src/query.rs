@@ -0,0 +1,560 @@
+use std::{collections::HashMap, fmt};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    app::{Card, Deck, Guess},
+    deck_tree::ancestor_names,
+    review_log::ReviewLogEntry,
+};
+
+/// a parsed search like `deck:Spanish tag:verbs is:due prop:ivl>30 added:7 rated:1:incorrect "front:*ar"`.
+/// Terms next to each other must all match, `or` between them needs either one, a leading `-` leaves
+/// matching cards out, and brackets group terms. `*` in a value matches any text.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Query {
+    All,                                // the empty query
+    Text(String),                       // the text appears on the front or the back
+    Deck(String),                       // the card is in the deck or one nested in it
+    Tag(String),                        // the card has the tag
+    Field(Side, String),                // the whole front or back matches
+    Is(CardState),
+    Prop(Property, Comparison, f64),
+    Added(u32),                         // created in the last days
+    Rated(u32, Option<Guess>),          // answered in the last days, with the given grade if there is one
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Side {
+    Front,
+    Back,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CardState {
+    Due,    // due now, new cards included
+    New,    // never reviewed
+    Review, // reviewed at least once
+    Suspended,
+}
+
+/// the numbers `prop:` compares
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Property {
+    Interval, // days between the last review and the next one
+    Due,      // calendar days until the card is due, 1 for tomorrow and negative when it is overdue
+    Ease,     // SM-2 easiness factor
+    Lapses,
+    Reps, // number of times the card was answered
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn compare(&self, left: f64, right: f64) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+/// why a query couldn't be parsed, `position` is the index of the offending character
+#[derive(Clone, PartialEq, Debug)]
+pub struct QueryError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl QueryError {
+    fn new(position: usize, message: impl Into<String>) -> QueryError {
+        QueryError { position, message: message.into() }
+    }
+
+    /// the query with a caret under the offending character on the line below
+    pub fn pointer(&self, query: &str) -> String {
+        format!("{query}\n{}^", " ".repeat(self.position))
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (at character {})", self.message, self.position + 1)
+    }
+}
+
+/// what the review log and the clock say about the cards, gathered once for all the cards a query is matched against
+pub struct QueryContext<'a> {
+    now: DateTime<Utc>,
+    reviews: HashMap<u64, Vec<&'a ReviewLogEntry>>, // the answers given to each card, by card id
+}
+
+impl<'a> QueryContext<'a> {
+    pub fn new(review_log: &'a [ReviewLogEntry], now: DateTime<Utc>) -> QueryContext<'a> {
+        let mut reviews: HashMap<u64, Vec<&ReviewLogEntry>> = HashMap::new();
+        for entry in review_log {
+            reviews.entry(entry.card_id).or_default().push(entry);
+        }
+        QueryContext { now, reviews }
+    }
+
+    fn reviews(&self, card: &Card) -> &[&'a ReviewLogEntry] {
+        self.reviews.get(&card.id).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(Query::All);
+        }
+        let mut parser = Parser { tokens, next: 0, end: input.chars().count() };
+        let query = parser.parse_or()?;
+        if let Some((_, position)) = parser.tokens.get(parser.next) {
+            return Err(QueryError::new(*position, "this bracket was never opened"));
+        }
+        Ok(query)
+    }
+
+    pub fn matches(&self, deck: &Deck, card: &Card, context: &QueryContext) -> bool {
+        match self {
+            Query::All => true,
+            Query::Text(text) => {
+                let pattern = format!("*{text}*");
+                wildcard_match(&pattern, &card.front) || wildcard_match(&pattern, &card.back)
+            }
+            Query::Deck(pattern) => {
+                wildcard_match(pattern, &deck.name)
+                    || ancestor_names(&deck.name).into_iter().any(|ancestor| wildcard_match(pattern, ancestor))
+            }
+            Query::Tag(pattern) => card.tags.iter().any(|tag| wildcard_match(pattern, tag)),
+            Query::Field(Side::Front, pattern) => wildcard_match(pattern, &card.front),
+            Query::Field(Side::Back, pattern) => wildcard_match(pattern, &card.back),
+            Query::Is(CardState::Due) => card.is_due(context.now),
+            Query::Is(CardState::New) => card.review.last_review.is_none(),
+            Query::Is(CardState::Review) => card.review.last_review.is_some(),
+            Query::Is(CardState::Suspended) => card.suspended,
+            Query::Prop(property, comparison, value) => {
+                let actual = match property {
+                    Property::Interval => card.review.interval as f64,
+                    Property::Due => (card.review.due.date_naive() - context.now.date_naive()).num_days() as f64,
+                    Property::Ease => card.review.ease_factor,
+                    Property::Lapses => card.review.lapses as f64,
+                    Property::Reps => context.reviews(card).len() as f64,
+                };
+                comparison.compare(actual, *value)
+            }
+            Query::Added(days) => {
+                let since = context.now - Duration::days(*days as i64);
+                card.created.is_some_and(|created| created >= since)
+            }
+            Query::Rated(days, grade) => {
+                let since = context.now - Duration::days(*days as i64);
+                context
+                    .reviews(card)
+                    .iter()
+                    .any(|entry| entry.timestamp >= since && grade.is_none_or(|grade| entry.grade == grade))
+            }
+            Query::Not(query) => !query.matches(deck, card, context),
+            Query::And(queries) => queries.iter().all(|query| query.matches(deck, card, context)),
+            Query::Or(queries) => queries.iter().any(|query| query.matches(deck, card, context)),
+        }
+    }
+}
+
+/// the (deck index, card index) of every card matching `query`, in deck order
+pub fn find_cards(decks: &[Deck], review_log: &[ReviewLogEntry], query: &Query, now: DateTime<Utc>) -> Vec<(usize, usize)> {
+    let context = QueryContext::new(review_log, now);
+    let mut found = Vec::new();
+    for (deck_index, deck) in decks.iter().enumerate() {
+        for (card_index, card) in deck.cards.iter().enumerate() {
+            if query.matches(deck, card, &context) {
+                found.push((deck_index, card_index));
+            }
+        }
+    }
+    found
+}
+
+/// whether all of `text` matches `pattern` ignoring case, `*` in the pattern matches any text
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let text = text.to_lowercase();
+    let mut pieces = pattern.split('*');
+    let first = pieces.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let pieces: Vec<&str> = pieces.collect();
+    let Some((last, middle)) = pieces.split_last() else {
+        // no `*` at all
+        return rest.is_empty();
+    };
+    for piece in middle {
+        let Some(index) = rest.find(piece) else {
+            return false;
+        };
+        rest = &rest[index + piece.len()..];
+    }
+    rest.ends_with(last)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Open,
+    Close,
+    Not,
+    And,
+    Or,
+    Word(Word),
+}
+
+/// a search term with the position each of its characters had in the query, quotes left out
+#[derive(Clone, PartialEq, Debug)]
+struct Word {
+    text: String,
+    positions: Vec<usize>,
+    end: usize, // the position right after the term
+}
+
+impl Word {
+    fn position(&self, char_index: usize) -> usize {
+        self.positions.get(char_index).copied().unwrap_or(self.end)
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while position < chars.len() {
+        let start = position;
+        match chars[position] {
+            c if c.is_whitespace() => position += 1,
+            '(' => {
+                tokens.push((Token::Open, start));
+                position += 1;
+            }
+            ')' => {
+                tokens.push((Token::Close, start));
+                position += 1;
+            }
+            '-' => {
+                tokens.push((Token::Not, start));
+                position += 1;
+            }
+            _ => {
+                let mut word = Word { text: String::new(), positions: Vec::new(), end: 0 };
+                let mut quoted = false;
+                while position < chars.len() {
+                    let c = chars[position];
+                    if c == '"' {
+                        let Some(length) = chars[position + 1..].iter().position(|c| *c == '"') else {
+                            return Err(QueryError::new(position, "this quote is never closed"));
+                        };
+                        for offset in 1..=length {
+                            word.text.push(chars[position + offset]);
+                            word.positions.push(position + offset);
+                        }
+                        position += length + 2;
+                        quoted = true;
+                    } else if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    } else {
+                        word.text.push(c);
+                        word.positions.push(position);
+                        position += 1;
+                    }
+                }
+                word.end = position;
+                let token = match word.text.to_lowercase().as_str() {
+                    "and" if !quoted => Token::And,
+                    "or" if !quoted => Token::Or,
+                    _ => Token::Word(word),
+                };
+                tokens.push((token, start));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    next: usize, // index of the next token to read
+    end: usize,  // the length of the query, where errors about missing terms point
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(token, _)| token)
+    }
+
+    fn parse_or(&mut self) -> Result<Query, QueryError> {
+        let mut queries = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next += 1;
+            queries.push(self.parse_and()?);
+        }
+        Ok(if queries.len() == 1 { queries.remove(0) } else { Query::Or(queries) })
+    }
+
+    fn parse_and(&mut self) -> Result<Query, QueryError> {
+        let mut queries = vec![self.parse_term()?];
+        loop {
+            match self.peek() {
+                None | Some(Token::Or) | Some(Token::Close) => break,
+                Some(Token::And) => {
+                    self.next += 1;
+                    queries.push(self.parse_term()?);
+                }
+                Some(_) => queries.push(self.parse_term()?),
+            }
+        }
+        Ok(if queries.len() == 1 { queries.remove(0) } else { Query::And(queries) })
+    }
+
+    fn parse_term(&mut self) -> Result<Query, QueryError> {
+        let Some((token, position)) = self.tokens.get(self.next).cloned() else {
+            return Err(QueryError::new(self.end, "expected a search term"));
+        };
+        self.next += 1;
+        match token {
+            Token::Not => Ok(Query::Not(Box::new(self.parse_term()?))),
+            Token::Open => {
+                let query = self.parse_or()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err(QueryError::new(position, "this bracket is never closed"));
+                }
+                self.next += 1;
+                Ok(query)
+            }
+            Token::Close => Err(QueryError::new(position, "expected a search term before the closing bracket")),
+            Token::And | Token::Or => Err(QueryError::new(position, "expected a search term before `and`/`or`")),
+            Token::Word(word) => parse_word(&word),
+        }
+    }
+}
+
+/// a single term, either plain text or `field:value`
+fn parse_word(word: &Word) -> Result<Query, QueryError> {
+    let Some((field, value)) = word.text.split_once(':') else {
+        return Ok(Query::Text(word.text.clone()));
+    };
+    // things like `12:30` are text
+    if field.is_empty() || !field.chars().all(char::is_alphabetic) {
+        return Ok(Query::Text(word.text.clone()));
+    }
+    let field_position = word.position(0);
+    let value_index = field.chars().count() + 1;
+    let value_position = word.position(value_index);
+    let non_empty = |what: &str| {
+        if value.is_empty() {
+            Err(QueryError::new(value_position, format!("expected {what} after `{field}:`")))
+        } else {
+            Ok(value.to_string())
+        }
+    };
+
+    match field.to_lowercase().as_str() {
+        "deck" => Ok(Query::Deck(non_empty("a deck name")?)),
+        "tag" => Ok(Query::Tag(non_empty("a tag")?)),
+        "front" => Ok(Query::Field(Side::Front, value.to_string())),
+        "back" => Ok(Query::Field(Side::Back, value.to_string())),
+        "is" => match value.to_lowercase().as_str() {
+            "due" => Ok(Query::Is(CardState::Due)),
+            "new" => Ok(Query::Is(CardState::New)),
+            "review" => Ok(Query::Is(CardState::Review)),
+            "suspended" => Ok(Query::Is(CardState::Suspended)),
+            _ => Err(QueryError::new(value_position, format!("unknown card state `{value}`, expected due, new, review or suspended"))),
+        },
+        "prop" => parse_property(value, word, value_index),
+        "added" => Ok(Query::Added(parse_days(value, value_position)?)),
+        "rated" => {
+            let (days, grade) = match value.split_once(':') {
+                Some((days, grade)) => (days, Some(grade)),
+                None => (value, None),
+            };
+            let days = parse_days(days, value_position)?;
+            let grade = match grade {
+                None => None,
+                Some(grade) => {
+                    let grade_position = word.position(value_index + value.chars().count() - grade.chars().count());
+                    Some(parse_grade(grade, grade_position)?)
+                }
+            };
+            Ok(Query::Rated(days, grade))
+        }
+        _ => Err(QueryError::new(
+            field_position,
+            format!("unknown search field `{field}`, expected deck, tag, is, prop, added, rated, front or back"),
+        )),
+    }
+}
+
+/// `prop:` values like `ivl>30`
+fn parse_property(value: &str, word: &Word, value_index: usize) -> Result<Query, QueryError> {
+    let name_length = value.chars().take_while(|c| c.is_alphabetic()).count();
+    let name: String = value.chars().take(name_length).collect();
+    let property = match name.to_lowercase().as_str() {
+        "ivl" => Property::Interval,
+        "due" => Property::Due,
+        "ease" => Property::Ease,
+        "lapses" => Property::Lapses,
+        "reps" => Property::Reps,
+        _ => {
+            return Err(QueryError::new(
+                word.position(value_index),
+                format!("unknown property `{name}`, expected ivl, due, ease, lapses or reps"),
+            ))
+        }
+    };
+
+    let rest: String = value.chars().skip(name_length).collect();
+    let operator_index = value_index + name_length;
+    let (comparison, operator_length) = if rest.starts_with(">=") {
+        (Comparison::GreaterOrEqual, 2)
+    } else if rest.starts_with("<=") {
+        (Comparison::LessOrEqual, 2)
+    } else if rest.starts_with("!=") {
+        (Comparison::NotEqual, 2)
+    } else if rest.starts_with('>') {
+        (Comparison::Greater, 1)
+    } else if rest.starts_with('<') {
+        (Comparison::Less, 1)
+    } else if rest.starts_with('=') {
+        (Comparison::Equal, 1)
+    } else {
+        return Err(QueryError::new(word.position(operator_index), "expected one of < <= = != >= > after the property"));
+    };
+
+    let number = &rest[operator_length..];
+    match number.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(Query::Prop(property, comparison, number)),
+        _ => Err(QueryError::new(word.position(operator_index + operator_length), format!("expected a number, found `{number}`"))),
+    }
+}
+
+fn parse_days(value: &str, position: usize) -> Result<u32, QueryError> {
+    match value.parse::<u32>() {
+        Ok(days) if days > 0 => Ok(days),
+        _ => Err(QueryError::new(position, format!("expected a number of days, found `{value}`"))),
+    }
+}
+
+fn parse_grade(value: &str, position: usize) -> Result<Guess, QueryError> {
+    match value.to_lowercase().as_str() {
+        "1" | "incorrect" | "again" => Ok(Guess::Incorrect),
+        "2" | "correct" | "good" => Ok(Guess::Correct),
+        "3" | "easy" => Ok(Guess::Easy),
+        _ => Err(QueryError::new(position, format!("unknown grade `{value}`, expected incorrect, correct or easy"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Query {
+        Query::Text(String::from(text))
+    }
+
+    #[test]
+    fn parsing() {
+        let cases = [
+            ("", Query::All),
+            ("  ", Query::All),
+            ("hola", text("hola")),
+            ("12:30", text("12:30")),
+            ("deck:Spanish", Query::Deck(String::from("Spanish"))),
+            ("DECK:\"Spanish verbs\"", Query::Deck(String::from("Spanish verbs"))),
+            ("\"front:*ar\"", Query::Field(Side::Front, String::from("*ar"))),
+            ("back:", Query::Field(Side::Back, String::new())),
+            ("is:due", Query::Is(CardState::Due)),
+            ("prop:ivl>=30", Query::Prop(Property::Interval, Comparison::GreaterOrEqual, 30.0)),
+            ("prop:ease!=2.5", Query::Prop(Property::Ease, Comparison::NotEqual, 2.5)),
+            ("added:7", Query::Added(7)),
+            ("rated:1:again", Query::Rated(1, Some(Guess::Incorrect))),
+            ("rated:3", Query::Rated(3, None)),
+            ("a b", Query::And(vec![text("a"), text("b")])),
+            ("a and b", Query::And(vec![text("a"), text("b")])),
+            ("a b or c", Query::Or(vec![Query::And(vec![text("a"), text("b")]), text("c")])),
+            ("a (b OR c)", Query::And(vec![text("a"), Query::Or(vec![text("b"), text("c")])])),
+            ("-tag:x", Query::Not(Box::new(Query::Tag(String::from("x"))))),
+            ("\"or\"", text("or")),
+        ];
+        for (input, query) in cases {
+            assert_eq!(Query::parse(input), Ok(query), "{input}");
+        }
+    }
+
+    #[test]
+    fn error_positions() {
+        let cases = [
+            ("\"open", 0),
+            ("a)", 1),
+            ("(a", 0),
+            ("a or", 4),
+            ("or a", 0),
+            ("-", 1),
+            ("deck:", 5),
+            ("tag: x", 4),
+            ("is:later", 3),
+            ("color:red", 0),
+            ("prop:size>3", 5),
+            ("prop:ivl~3", 8),
+            ("prop:ivl>x", 9),
+            ("added:0", 6),
+            ("rated:2:meh", 8),
+            ("x \"deck:\"", 9),
+        ];
+        for (input, position) in cases {
+            let error = Query::parse(input).expect_err(input);
+            assert_eq!(error.position, position, "{input}: {error}");
+        }
+    }
+
+    #[test]
+    fn error_pointer() {
+        let error = Query::parse("is:later").unwrap_err();
+        assert_eq!(error.pointer("is:later"), "is:later\n   ^");
+    }
+
+    #[test]
+    fn wildcards() {
+        let cases = [
+            ("hola", "hola", true),
+            ("hola", "HOLA", true),
+            ("hola", "hola!", false),
+            ("*ar", "hablar", true),
+            ("*ar", "hablas", false),
+            ("ha*", "hablar", true),
+            ("h*l*r", "hablar", true),
+            ("h*x*r", "hablar", false),
+            ("*", "", true),
+            ("a*a", "a", false),
+            ("*bla*", "hablar", true),
+            ("", "", true),
+            ("", "x", false),
+        ];
+        for (pattern, text, matches) in cases {
+            assert_eq!(wildcard_match(pattern, text), matches, "{pattern} {text}");
+        }
+    }
+}
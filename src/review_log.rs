@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app::Guess;
+
+/// one answer given in learning mode, the history statistics are built from.
+/// Entries are appended to the end of the log, even imported ones that are older, so undo can take the last ones off again.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ReviewLogEntry {
+    pub card_id: u64,
+    pub deck_id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub grade: Guess,
+    pub previous_interval: u32, // days
+    pub new_interval: u32,      // days
+    pub time_spent_ms: u64,     // how long the card was showing before it was graded
+}
@@ -0,0 +1,392 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app::Guess;
+
+/// the scheduling information of a card.
+/// Every scheduler reads and writes the fields it needs, so a deck can switch algorithm
+/// without resetting the progress of its cards.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct ReviewState {
+    #[serde(default = "default_ease_factor")]
+    pub ease_factor: f64, // SM-2 easiness factor, never drops below 1.3
+    #[serde(default)]
+    pub interval: u32, // days until the card is due again after the last review
+    #[serde(default)]
+    pub repetitions: u32, // number of correct answers in a row
+    #[serde(default)]
+    pub lapses: u32, // number of times the card was forgotten after being learned
+    #[serde(default)]
+    pub leitner_box: u8, // the Leitner box the card is in, 0 when it was never reviewed with Leitner
+    #[serde(default)]
+    pub stability: f64, // FSRS memory stability in days, 0 when it was never reviewed with FSRS
+    #[serde(default)]
+    pub difficulty: f64, // FSRS difficulty between 1 and 10
+    #[serde(default = "Utc::now")]
+    pub due: DateTime<Utc>, // when the card should be shown next
+    #[serde(default)]
+    pub last_review: Option<DateTime<Utc>>,
+}
+
+fn default_ease_factor() -> f64 {
+    2.5
+}
+
+impl Default for ReviewState {
+    fn default() -> ReviewState {
+        ReviewState::new(Utc::now())
+    }
+}
+
+impl ReviewState {
+    /// the state of a card that has never been reviewed and is due at `now`
+    pub fn new(now: DateTime<Utc>) -> ReviewState {
+        ReviewState {
+            ease_factor: default_ease_factor(),
+            interval: 0,
+            repetitions: 0,
+            lapses: 0,
+            leitner_box: 0,
+            stability: 0.0,
+            difficulty: 0.0,
+            due: now,
+            last_review: None,
+        }
+    }
+
+    fn reviewed(mut self, interval: u32, now: DateTime<Utc>) -> ReviewState {
+        self.interval = interval;
+        self.due = now + Duration::days(interval as i64);
+        self.last_review = Some(now);
+        self
+    }
+}
+
+/// a spaced-repetition algorithm deciding when a card is shown again
+pub trait Scheduler {
+    fn name(&self) -> &'static str;
+
+    /// returns the state of a card after it was answered with `guess` at `now`, including its new due date
+    fn schedule(&self, state: &ReviewState, guess: Guess, now: DateTime<Utc>) -> ReviewState;
+}
+
+/// the scheduler used by a deck
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum SchedulerKind {
+    #[default]
+    Sm2,
+    Leitner,
+    Fsrs,
+}
+
+impl SchedulerKind {
+    pub fn scheduler(&self) -> &'static dyn Scheduler {
+        match self {
+            SchedulerKind::Sm2 => &Sm2,
+            SchedulerKind::Leitner => &Leitner,
+            SchedulerKind::Fsrs => &Fsrs,
+        }
+    }
+
+    pub fn next(&self) -> SchedulerKind {
+        match self {
+            SchedulerKind::Sm2 => SchedulerKind::Leitner,
+            SchedulerKind::Leitner => SchedulerKind::Fsrs,
+            SchedulerKind::Fsrs => SchedulerKind::Sm2,
+        }
+    }
+}
+
+/// the SuperMemo-2 algorithm.
+/// The guesses map onto the SM-2 quality grades incorrect = 1, correct = 4 and easy = 5.
+pub struct Sm2;
+
+impl Scheduler for Sm2 {
+    fn name(&self) -> &'static str {
+        "SM-2"
+    }
+
+    fn schedule(&self, state: &ReviewState, guess: Guess, now: DateTime<Utc>) -> ReviewState {
+        let quality: f64 = match guess {
+            Guess::Incorrect => 1.0,
+            Guess::Correct => 4.0,
+            Guess::Easy => 5.0,
+            Guess::None => return *state,
+        };
+
+        let mut new_state = *state;
+        let interval = if quality < 3.0 {
+            if new_state.repetitions > 0 {
+                new_state.lapses += 1;
+            }
+            new_state.repetitions = 0;
+            1
+        } else {
+            let interval = match new_state.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (new_state.interval as f64 * new_state.ease_factor).round() as u32,
+            };
+            new_state.repetitions += 1;
+            interval
+        };
+
+        new_state.ease_factor += 0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02);
+        if new_state.ease_factor < 1.3 {
+            new_state.ease_factor = 1.3;
+        }
+
+        new_state.reviewed(interval, now)
+    }
+}
+
+/// the Leitner box system.
+/// A correct answer moves the card up one box, an easy one two boxes and an incorrect one back to the first box.
+pub struct Leitner;
+
+/// days between reviews for each Leitner box
+pub const LEITNER_INTERVALS: [u32; 5] = [1, 2, 4, 8, 16];
+
+impl Scheduler for Leitner {
+    fn name(&self) -> &'static str {
+        "Leitner"
+    }
+
+    fn schedule(&self, state: &ReviewState, guess: Guess, now: DateTime<Utc>) -> ReviewState {
+        let mut new_state = *state;
+        // cards that were never in a box start in the first one
+        let current_box = new_state.leitner_box.max(1);
+        let last_box = LEITNER_INTERVALS.len() as u8;
+
+        new_state.leitner_box = match guess {
+            Guess::Incorrect => {
+                if new_state.repetitions > 0 {
+                    new_state.lapses += 1;
+                }
+                new_state.repetitions = 0;
+                1
+            }
+            Guess::Correct => {
+                new_state.repetitions += 1;
+                (current_box + 1).min(last_box)
+            }
+            Guess::Easy => {
+                new_state.repetitions += 1;
+                (current_box + 2).min(last_box)
+            }
+            Guess::None => return *state,
+        };
+
+        let interval = LEITNER_INTERVALS[new_state.leitner_box as usize - 1];
+        new_state.reviewed(interval, now)
+    }
+}
+
+/// the Free Spaced Repetition Scheduler (FSRS-4.5) with its default parameters.
+/// The guesses map onto the FSRS ratings incorrect = again, correct = good and easy = easy.
+pub struct Fsrs;
+
+const FSRS_WEIGHTS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072,
+    0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+const FSRS_DECAY: f64 = -0.5;
+const FSRS_FACTOR: f64 = 19.0 / 81.0;
+const FSRS_DESIRED_RETENTION: f64 = 0.9;
+
+impl Fsrs {
+    fn initial_stability(rating: f64) -> f64 {
+        FSRS_WEIGHTS[rating as usize - 1].max(0.1)
+    }
+
+    fn initial_difficulty(rating: f64) -> f64 {
+        (FSRS_WEIGHTS[4] - (rating - 3.0) * FSRS_WEIGHTS[5]).clamp(1.0, 10.0)
+    }
+
+    fn retrievability(elapsed_days: f64, stability: f64) -> f64 {
+        (1.0 + FSRS_FACTOR * elapsed_days / stability).powf(FSRS_DECAY)
+    }
+
+    fn next_difficulty(difficulty: f64, rating: f64) -> f64 {
+        let next = difficulty - FSRS_WEIGHTS[6] * (rating - 3.0);
+        // mean reversion towards the difficulty of a card first rated good
+        (FSRS_WEIGHTS[7] * FSRS_WEIGHTS[4] + (1.0 - FSRS_WEIGHTS[7]) * next).clamp(1.0, 10.0)
+    }
+
+    fn next_recall_stability(difficulty: f64, stability: f64, retrievability: f64, rating: f64) -> f64 {
+        let easy_bonus = if rating == 4.0 { FSRS_WEIGHTS[16] } else { 1.0 };
+        stability
+            * (FSRS_WEIGHTS[8].exp()
+                * (11.0 - difficulty)
+                * stability.powf(-FSRS_WEIGHTS[9])
+                * ((FSRS_WEIGHTS[10] * (1.0 - retrievability)).exp() - 1.0)
+                * easy_bonus
+                + 1.0)
+    }
+
+    fn next_forget_stability(difficulty: f64, stability: f64, retrievability: f64) -> f64 {
+        let next = FSRS_WEIGHTS[11]
+            * difficulty.powf(-FSRS_WEIGHTS[12])
+            * ((stability + 1.0).powf(FSRS_WEIGHTS[13]) - 1.0)
+            * (FSRS_WEIGHTS[14] * (1.0 - retrievability)).exp();
+        next.min(stability)
+    }
+
+    fn interval(stability: f64) -> u32 {
+        let interval = stability / FSRS_FACTOR * (FSRS_DESIRED_RETENTION.powf(1.0 / FSRS_DECAY) - 1.0);
+        (interval.round() as u32).max(1)
+    }
+}
+
+impl Scheduler for Fsrs {
+    fn name(&self) -> &'static str {
+        "FSRS"
+    }
+
+    fn schedule(&self, state: &ReviewState, guess: Guess, now: DateTime<Utc>) -> ReviewState {
+        let rating: f64 = match guess {
+            Guess::Incorrect => 1.0,
+            Guess::Correct => 3.0,
+            Guess::Easy => 4.0,
+            Guess::None => return *state,
+        };
+
+        let mut new_state = *state;
+        match new_state.last_review {
+            None => {
+                new_state.stability = Fsrs::initial_stability(rating);
+                new_state.difficulty = Fsrs::initial_difficulty(rating);
+            }
+            Some(last_review) => {
+                // cards reviewed by another scheduler so far have no memory state yet,
+                // their current interval is the best estimate of the stability
+                if new_state.stability <= 0.0 {
+                    new_state.stability = new_state.interval.max(1) as f64;
+                    new_state.difficulty = Fsrs::initial_difficulty(3.0);
+                }
+                let elapsed_days = (now - last_review).num_seconds().max(0) as f64 / 86_400.0;
+                let retrievability = Fsrs::retrievability(elapsed_days, new_state.stability);
+                new_state.stability = if rating == 1.0 {
+                    Fsrs::next_forget_stability(new_state.difficulty, new_state.stability, retrievability)
+                } else {
+                    Fsrs::next_recall_stability(new_state.difficulty, new_state.stability, retrievability, rating)
+                };
+                new_state.difficulty = Fsrs::next_difficulty(new_state.difficulty, rating);
+            }
+        }
+
+        if rating == 1.0 {
+            if new_state.repetitions > 0 {
+                new_state.lapses += 1;
+            }
+            new_state.repetitions = 0;
+        } else {
+            new_state.repetitions += 1;
+        }
+
+        let interval = Fsrs::interval(new_state.stability);
+        new_state.reviewed(interval, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()
+    }
+
+    /// answers a new card with each guess in turn, a day after the card became due
+    fn review(scheduler: &dyn Scheduler, guesses: &[Guess]) -> ReviewState {
+        let mut state = ReviewState::new(now());
+        for guess in guesses {
+            let at = state.due;
+            state = scheduler.schedule(&state, *guess, at);
+        }
+        state
+    }
+
+    #[test]
+    fn sm2_intervals_and_ease() {
+        let cases = [
+            (&[Guess::Correct][..], 1, 2.5, 1, 0),
+            (&[Guess::Correct, Guess::Correct], 6, 2.5, 2, 0),
+            (&[Guess::Correct, Guess::Correct, Guess::Correct], 15, 2.5, 3, 0),
+            (&[Guess::Easy], 1, 2.6, 1, 0),
+            (&[Guess::Easy, Guess::Easy, Guess::Easy], 16, 2.8, 3, 0),
+            (&[Guess::Incorrect], 1, 1.96, 0, 0),
+            (&[Guess::Correct, Guess::Correct, Guess::Incorrect], 1, 1.96, 0, 1),
+        ];
+        for (guesses, interval, ease_factor, repetitions, lapses) in cases {
+            let state = review(&Sm2, guesses);
+            assert_eq!(state.interval, interval, "{guesses:?}");
+            assert!((state.ease_factor - ease_factor).abs() < 1e-9, "{guesses:?}: {}", state.ease_factor);
+            assert_eq!(state.repetitions, repetitions, "{guesses:?}");
+            assert_eq!(state.lapses, lapses, "{guesses:?}");
+        }
+    }
+
+    #[test]
+    fn sm2_ease_never_drops_below_minimum() {
+        let state = review(&Sm2, &[Guess::Incorrect; 10]);
+        assert_eq!(state.ease_factor, 1.3);
+    }
+
+    #[test]
+    fn leitner_boxes() {
+        let cases = [
+            (&[Guess::Correct][..], 2),
+            (&[Guess::Easy], 3),
+            (&[Guess::Easy, Guess::Easy], 5),
+            (&[Guess::Easy, Guess::Easy, Guess::Correct], 5),
+            (&[Guess::Incorrect], 1),
+            (&[Guess::Easy, Guess::Correct, Guess::Incorrect], 1),
+        ];
+        for (guesses, leitner_box) in cases {
+            let state = review(&Leitner, guesses);
+            assert_eq!(state.leitner_box, leitner_box, "{guesses:?}");
+            assert_eq!(state.interval, LEITNER_INTERVALS[leitner_box as usize - 1], "{guesses:?}");
+        }
+        assert_eq!(review(&Leitner, &[Guess::Easy, Guess::Incorrect]).lapses, 1);
+    }
+
+    #[test]
+    fn fsrs_first_review() {
+        let cases = [
+            (Guess::Incorrect, FSRS_WEIGHTS[0], FSRS_WEIGHTS[4] + 2.0 * FSRS_WEIGHTS[5]),
+            (Guess::Correct, FSRS_WEIGHTS[2], FSRS_WEIGHTS[4]),
+            (Guess::Easy, FSRS_WEIGHTS[3], FSRS_WEIGHTS[4] - FSRS_WEIGHTS[5]),
+        ];
+        for (guess, stability, difficulty) in cases {
+            let state = review(&Fsrs, &[guess]);
+            assert_eq!(state.stability, stability, "{guess:?}");
+            assert_eq!(state.difficulty, difficulty, "{guess:?}");
+            // at the desired retention of 90% the interval is the stability
+            assert_eq!(state.interval, (stability.round() as u32).max(1), "{guess:?}");
+        }
+    }
+
+    #[test]
+    fn fsrs_lapse() {
+        let learned = review(&Fsrs, &[Guess::Correct, Guess::Correct]);
+        assert!(learned.stability > FSRS_WEIGHTS[2]);
+
+        let lapsed = Fsrs.schedule(&learned, Guess::Incorrect, learned.due);
+        assert!(lapsed.stability < learned.stability);
+        assert!(lapsed.difficulty > learned.difficulty);
+        assert_eq!(lapsed.lapses, 1);
+        assert_eq!(lapsed.repetitions, 0);
+        assert_eq!(lapsed.interval, Fsrs::interval(lapsed.stability));
+        assert_eq!(lapsed.due, learned.due + Duration::days(lapsed.interval as i64));
+    }
+
+    #[test]
+    fn unanswered_cards_keep_their_state() {
+        let state = review(&Sm2, &[Guess::Correct]);
+        for scheduler in [SchedulerKind::Sm2, SchedulerKind::Leitner, SchedulerKind::Fsrs] {
+            assert_eq!(scheduler.scheduler().schedule(&state, Guess::None, now()), state);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::app::{Card, Deck};
+
+/// a card matching the search, with the indices to find it again in `App::decks`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SearchResult {
+    pub deck_index: usize,
+    pub card_index: usize,
+    pub score: i64, // higher is a better match
+}
+
+/// finds the cards whose front, back or tags match every word of `query`, best matches first
+pub fn search(decks: &[Deck], query: &str) -> Vec<SearchResult> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut results = Vec::new();
+    for (deck_index, deck) in decks.iter().enumerate() {
+        for (card_index, card) in deck.cards.iter().enumerate() {
+            if let Some(score) = card_score(card, &words) {
+                results.push(SearchResult { deck_index, card_index, score });
+            }
+        }
+    }
+    // the sort is stable, so equally good matches stay in deck order
+    results.sort_by_key(|result| -result.score);
+    results
+}
+
+/// the sum of how well each word matches the card, `None` when a word matches none of its fields
+fn card_score(card: &Card, words: &[String]) -> Option<i64> {
+    let fields = [card.front.to_lowercase(), card.back.to_lowercase()];
+    let tags: Vec<String> = card.tags.iter().map(|tag| tag.to_lowercase()).collect();
+
+    let mut total = 0;
+    for word in words {
+        let best = fields
+            .iter()
+            .chain(tags.iter())
+            .filter_map(|field| fuzzy_score(word, field))
+            .max()?;
+        total += best;
+    }
+    Some(total)
+}
+
+/// scores `text` containing the characters of `word` in order, not necessarily next to each other.
+/// Characters following each other and matches at the start of words score higher, gaps cost a little,
+/// and a word found as a whole gets a bonus so exact matches come first.
+fn fuzzy_score(word: &str, text: &str) -> Option<i64> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for c in word.chars() {
+        let offset = chars[position..].iter().position(|text_char| *text_char == c)?;
+        let index = position + offset;
+        score += 1;
+        if previous_match.is_some_and(|previous| previous + 1 == index) {
+            score += 5;
+        }
+        if index == 0 || !chars[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+        if previous_match.is_some() {
+            score -= offset.min(5) as i64;
+        }
+        previous_match = Some(index);
+        position = index + 1;
+    }
+
+    if text.contains(word) {
+        score += 10;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use chrono::Utc;
+
+    use super::*;
+
+    fn deck(cards: &[(&str, &str, &[&str])]) -> Deck {
+        Deck {
+            id: 1,
+            name: String::from("Spanish"),
+            cards: cards
+                .iter()
+                .map(|(front, back, tags)| {
+                    let mut card = Card::new(front.to_string(), back.to_string());
+                    card.tags = tags.iter().map(|tag| tag.to_string()).collect::<BTreeSet<_>>();
+                    card
+                })
+                .collect(),
+            date_last_learned: Utc::now(),
+            scheduler: Default::default(),
+            review_mode: Default::default(),
+            answer_options: Default::default(),
+            collapsed: false,
+        }
+    }
+
+    fn fronts(decks: &[Deck], query: &str) -> Vec<String> {
+        search(decks, query).iter().map(|result| decks[result.deck_index].cards[result.card_index].front.clone()).collect()
+    }
+
+    #[test]
+    fn scores() {
+        let cases = [
+            ("cat", "cat", true),
+            ("cat", "concatenate", true),
+            ("cat", "c a t", true),
+            ("cat", "act", false),
+            ("cat", "ca", false),
+            ("", "anything", true),
+        ];
+        for (word, text, matches) in cases {
+            assert_eq!(fuzzy_score(word, text).is_some(), matches, "{word} {text}");
+        }
+        // whole words beat the same letters spread out, and word starts beat the middle of words
+        assert!(fuzzy_score("cat", "cat") > fuzzy_score("cat", "c a t"));
+        assert!(fuzzy_score("cat", "the cat") > fuzzy_score("cat", "concatenate"));
+        assert!(fuzzy_score("cat", "c-a-t") > fuzzy_score("cat", "cxxxxxxxxxaxxxxxxxxxt"));
+    }
+
+    #[test]
+    fn ranking() {
+        let decks = vec![
+            deck(&[("gato", "cat", &[]), ("concatenar", "to concatenate", &[]), ("hola", "hello", &["greeting"])]),
+            deck(&[("Cat", "gato", &["animals"]), ("perro", "dog", &["animals"])]),
+        ];
+        assert_eq!(fronts(&decks, "cat"), ["gato", "Cat", "concatenar"]);
+        assert_eq!(fronts(&decks, "CAT gato"), ["gato", "Cat"]);
+        assert_eq!(fronts(&decks, "anim"), ["Cat", "perro"]);
+        assert_eq!(fronts(&decks, "greet hello"), ["hola"]);
+    }
+
+    #[test]
+    fn no_matches() {
+        let decks = vec![deck(&[("gato", "cat", &["animals"])])];
+        for query in ["", "   ", "dog", "cat dog", "tac"] {
+            assert!(search(&decks, query).is_empty(), "{query:?}");
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use chrono::{DateTime, Local, NaiveDate, Utc};
+
+use crate::{
+    app::{Deck, Guess},
+    review_log::ReviewLogEntry,
+};
+
+/// number of days shown in the history and forecast charts
+pub const STATS_DAYS: usize = 30;
+
+/// upper bounds (inclusive, in days) and labels of the interval distribution buckets
+const INTERVAL_BUCKETS: [(u32, &str); 8] = [
+    (0, "new"),
+    (1, "1d"),
+    (3, "2-3d"),
+    (7, "1w"),
+    (14, "2w"),
+    (30, "1m"),
+    (90, "3m"),
+    (u32::MAX, "3m+"),
+];
+
+/// numbers shown on the statistics screen, computed from the review log and the cards of some decks
+pub struct Statistics {
+    pub total_cards: usize,
+    pub total_reviews: usize,
+    pub reviews_today: u64,
+    pub reviews_per_day: Vec<u64>, // the last `STATS_DAYS` days, oldest first
+    pub time_spent_per_day: Vec<u64>, // seconds per day for the last `STATS_DAYS` days, oldest first
+    pub total_time_spent_ms: u64,
+    pub retention: Option<f64>, // share of reviews in the last `STATS_DAYS` days that were not answered incorrectly
+    pub due_forecast: Vec<u64>, // cards due on each of the next `STATS_DAYS` days, overdue cards count for today
+    pub interval_distribution: Vec<(&'static str, u64)>,
+}
+
+impl Statistics {
+    pub fn compute(decks: &[&Deck], review_log: &[ReviewLogEntry], now: DateTime<Utc>) -> Statistics {
+        let today = local_date(now);
+        let deck_ids: Vec<u64> = decks.iter().map(|deck| deck.id).collect();
+
+        let mut reviews_per_day = vec![0; STATS_DAYS];
+        let mut time_spent_per_day = vec![0; STATS_DAYS];
+        let mut total_reviews = 0;
+        let mut total_time_spent_ms = 0;
+        let mut recent_reviews = 0;
+        let mut recent_recalled = 0;
+
+        for entry in review_log.iter().filter(|entry| deck_ids.contains(&entry.deck_id)) {
+            total_reviews += 1;
+            total_time_spent_ms += entry.time_spent_ms;
+
+            let days_ago = (today - local_date(entry.timestamp)).num_days();
+            if days_ago < 0 || days_ago >= STATS_DAYS as i64 {
+                continue;
+            }
+            let day = STATS_DAYS - 1 - days_ago as usize;
+            reviews_per_day[day] += 1;
+            time_spent_per_day[day] += entry.time_spent_ms / 1000;
+            recent_reviews += 1;
+            if entry.grade != Guess::Incorrect {
+                recent_recalled += 1;
+            }
+        }
+
+        let mut due_forecast = vec![0; STATS_DAYS];
+        let mut interval_distribution: Vec<(&'static str, u64)> =
+            INTERVAL_BUCKETS.iter().map(|(_, label)| (*label, 0)).collect();
+        let mut total_cards = 0;
+
+        for card in decks.iter().flat_map(|deck| deck.cards.iter()) {
+            total_cards += 1;
+
+            let days_until_due = (local_date(card.review.due) - today).num_days().max(0);
+            if !card.suspended && days_until_due < STATS_DAYS as i64 {
+                due_forecast[days_until_due as usize] += 1;
+            }
+
+            let interval = if card.review.last_review.is_none() { 0 } else { card.review.interval.max(1) };
+            if let Some(bucket) = INTERVAL_BUCKETS.iter().position(|(max, _)| interval <= *max) {
+                interval_distribution[bucket].1 += 1;
+            }
+        }
+
+        Statistics {
+            total_cards,
+            total_reviews,
+            reviews_today: reviews_per_day[STATS_DAYS - 1],
+            reviews_per_day,
+            time_spent_per_day,
+            total_time_spent_ms,
+            retention: (recent_reviews > 0).then(|| recent_recalled as f64 / recent_reviews as f64),
+            due_forecast,
+            interval_distribution,
+        }
+    }
+}
+
+fn local_date(timestamp: DateTime<Utc>) -> NaiveDate {
+    timestamp.with_timezone(&Local).date_naive()
+}
+
+/// formats a duration in milliseconds as hours and minutes
+pub fn format_duration(ms: u64) -> String {
+    let minutes = ms / 60_000;
+    if minutes >= 60 {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m {}s", minutes, (ms / 1000) % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+    use crate::app::Card;
+
+    fn deck(id: u64, cards: Vec<Card>) -> Deck {
+        Deck {
+            id,
+            name: id.to_string(),
+            cards,
+            date_last_learned: Utc::now(),
+            scheduler: Default::default(),
+            review_mode: Default::default(),
+            answer_options: Default::default(),
+            collapsed: false,
+        }
+    }
+
+    fn entry(deck_id: u64, timestamp: DateTime<Utc>, grade: Guess) -> ReviewLogEntry {
+        ReviewLogEntry {
+            card_id: 0,
+            deck_id,
+            timestamp,
+            grade,
+            previous_interval: 0,
+            new_interval: 1,
+            time_spent_ms: 4_000,
+        }
+    }
+
+    #[test]
+    fn statistics() {
+        // noon local time, so whole days back and forth stay on their dates even across a DST change
+        let now = Local.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).single().unwrap().with_timezone(&Utc);
+        let days = |days: i64| now + Duration::days(days);
+
+        let card = |due: Option<(i64, u32)>, suspended: bool| {
+            let mut card = Card::new(String::from("front"), String::from("back"));
+            card.review.due = now;
+            if let Some((due, interval)) = due {
+                card.review.last_review = Some(days(-1));
+                card.review.due = days(due);
+                card.review.interval = interval;
+            }
+            card.suspended = suspended;
+            card
+        };
+        let studied = deck(
+            1,
+            vec![
+                card(None, false),
+                card(Some((-3, 1)), false),
+                card(Some((0, 2)), false),
+                card(Some((5, 10)), false),
+                card(Some((1, 200)), true),
+            ],
+        );
+        let other = deck(2, vec![card(None, false)]);
+
+        let review_log = [
+            entry(1, now, Guess::Correct),
+            entry(1, now - Duration::hours(1), Guess::Incorrect),
+            entry(1, days(-1), Guess::Easy),
+            entry(1, days(-29), Guess::Correct),
+            entry(1, days(-30), Guess::Incorrect), // too old for the charts and the retention
+            entry(2, now, Guess::Incorrect),       // another deck
+        ];
+        let statistics = Statistics::compute(&[&studied], &review_log, now);
+
+        assert_eq!(statistics.total_cards, 5);
+        assert_eq!(statistics.total_reviews, 5);
+        assert_eq!(statistics.total_time_spent_ms, 20_000);
+        assert_eq!(statistics.reviews_today, 2);
+        assert_eq!(statistics.reviews_per_day[STATS_DAYS - 1], 2);
+        assert_eq!(statistics.reviews_per_day[STATS_DAYS - 2], 1);
+        assert_eq!(statistics.reviews_per_day[0], 1);
+        assert_eq!(statistics.reviews_per_day.iter().sum::<u64>(), 4);
+        assert_eq!(statistics.time_spent_per_day[STATS_DAYS - 1], 8);
+        assert_eq!(statistics.retention, Some(0.75));
+
+        // new cards are due now, the overdue one counts for today and the suspended one not at all
+        let mut forecast = vec![0; STATS_DAYS];
+        forecast[0] = 3;
+        forecast[5] = 1;
+        assert_eq!(statistics.due_forecast, forecast);
+
+        let buckets: Vec<(&str, u64)> =
+            statistics.interval_distribution.into_iter().filter(|(_, count)| *count > 0).collect();
+        assert_eq!(buckets, [("new", 1), ("1d", 1), ("2-3d", 1), ("2w", 1), ("3m+", 1)]);
+
+        let empty = Statistics::compute(&[&other], &[], now);
+        assert_eq!((empty.total_reviews, empty.retention), (0, None));
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{app::Deck, review_log::ReviewLogEntry};
+
+/// version of the on-disk collection format, bumped whenever the layout changes
+pub const COLLECTION_VERSION: u32 = 1;
+
+const COLLECTION_FILE_NAME: &str = "collection.json";
+
+/// everything that is persisted between sessions
+#[derive(Deserialize, Default)]
+pub struct Collection {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub decks: Vec<Deck>,
+    #[serde(default)]
+    pub review_log: Vec<ReviewLogEntry>,
+}
+
+/// borrowed view of the collection used when saving, so the decks don't need to be cloned
+#[derive(Serialize)]
+struct CollectionRef<'a> {
+    version: u32,
+    decks: &'a [Deck],
+    review_log: &'a [ReviewLogEntry],
+}
+
+/// the location of the collection file.
+/// `OK_CARD_COLLECTION` overrides it, otherwise the XDG data directory is used.
+pub fn default_collection_path() -> PathBuf {
+    if let Some(path) = env::var_os("OK_CARD_COLLECTION") {
+        return PathBuf::from(path);
+    }
+    let data_dir = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => match env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(".local").join("share"),
+            None => PathBuf::from("."),
+        },
+    };
+    data_dir.join("ok-card").join(COLLECTION_FILE_NAME)
+}
+
+/// the directory the key bindings and other settings are read from.
+/// `OK_CARD_CONFIG_DIR` overrides it, otherwise the XDG config directory is used.
+pub fn config_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("OK_CARD_CONFIG_DIR") {
+        return PathBuf::from(dir);
+    }
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => match env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(".config"),
+            None => PathBuf::from("."),
+        },
+    };
+    config_dir.join("ok-card")
+}
+
+/// loads the collection from `path`, a missing file is treated as an empty collection
+pub fn load(path: &Path) -> io::Result<Collection> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Collection::default()),
+        Err(err) => return Err(err),
+    };
+    let collection: Collection = serde_json::from_str(&contents).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("could not read collection {}: {err}", path.display()),
+        )
+    })?;
+    if collection.version > COLLECTION_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "collection {} was written by a newer version of ok-card (format {})",
+                path.display(),
+                collection.version
+            ),
+        ));
+    }
+    Ok(collection)
+}
+
+/// saves the decks and the review log to `path`.
+/// The data is written to a temporary file next to the target which is then renamed over it,
+/// so a crash half way through a save never leaves a truncated collection behind.
+pub fn save(path: &Path, decks: &[Deck], review_log: &[ReviewLogEntry]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    let collection = CollectionRef { version: COLLECTION_VERSION, decks, review_log };
+    let json = serde_json::to_vec_pretty(&collection)?;
+
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(&json)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)
+}
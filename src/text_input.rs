@@ -0,0 +1,314 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// an editable text with a cursor, used for every text field like the card editor, the deck name and the queries.
+/// The cursor moves over and deletes whole graphemes, so accented letters written with combining marks
+/// and emoji made of several code points behave like a single character.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct TextInput {
+    text: String,
+    cursor: usize, // byte offset into `text`, always at the start of a grapheme or at the end
+    multiline: bool, // whether newlines can be typed or pasted, they turn into spaces otherwise
+}
+
+impl TextInput {
+    pub fn multiline() -> TextInput {
+        TextInput { multiline: true, ..TextInput::default() }
+    }
+
+    pub fn single_line() -> TextInput {
+        TextInput::default()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// replaces the text, with the cursor at its end
+    pub fn set_text(&mut self, text: &str) {
+        self.text = String::new();
+        self.cursor = 0;
+        self.insert(text);
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text("");
+    }
+
+    /// inserts typed or pasted text at the cursor. Windows line endings become newlines,
+    /// tabs become spaces and other control characters are dropped.
+    pub fn insert(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n").replace('\t', "    ");
+        let text: String = text
+            .chars()
+            .filter_map(|c| match c {
+                '\n' if self.multiline => Some('\n'),
+                '\n' => Some(' '),
+                c if c.is_control() => None,
+                c => Some(c),
+            })
+            .collect();
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// edits the text or moves the cursor for `key`, returns `false` for keys it has no use for like a plain ENTER
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Enter if self.multiline && (alt || key.modifiers.contains(KeyModifiers::SHIFT)) => self.insert("\n"),
+            KeyCode::Char('w') if control => self.delete_word_before(),
+            KeyCode::Char(c) if !control && !alt => self.insert(c.encode_utf8(&mut [0; 4])),
+            KeyCode::Backspace if control || alt => self.delete_word_before(),
+            KeyCode::Backspace => self.delete_before(),
+            KeyCode::Delete => self.delete_after(),
+            KeyCode::Left if control || alt => self.cursor = self.word_start_before(),
+            KeyCode::Right if control || alt => self.cursor = self.word_end_after(),
+            KeyCode::Left => self.cursor = self.previous_boundary(),
+            KeyCode::Right => self.cursor = self.next_boundary(),
+            KeyCode::Home if control => self.cursor = 0,
+            KeyCode::End if control => self.cursor = self.text.len(),
+            KeyCode::Home => self.cursor = self.line_start(self.cursor),
+            KeyCode::End => self.cursor = self.line_end(self.cursor),
+            KeyCode::Up if self.multiline => self.move_line(-1),
+            KeyCode::Down if self.multiline => self.move_line(1),
+            _ => return false,
+        }
+        true
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.text[..self.cursor].grapheme_indices(true).next_back().map_or(0, |(index, _)| index)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.text[self.cursor..].graphemes(true).next().map_or(self.cursor, |grapheme| self.cursor + grapheme.len())
+    }
+
+    fn delete_before(&mut self) {
+        let start = self.previous_boundary();
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    fn delete_after(&mut self) {
+        let end = self.next_boundary();
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    fn delete_word_before(&mut self) {
+        let start = self.word_start_before();
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// the start of the word before the cursor, skipping the spaces and punctuation in between
+    fn word_start_before(&self) -> usize {
+        let graphemes: Vec<(usize, &str)> = self.text[..self.cursor].grapheme_indices(true).collect();
+        let mut position = graphemes.len();
+        while position > 0 && !is_word(graphemes[position - 1].1) {
+            position -= 1;
+        }
+        while position > 0 && is_word(graphemes[position - 1].1) {
+            position -= 1;
+        }
+        graphemes.get(position).map_or(self.cursor, |(index, _)| *index)
+    }
+
+    /// the end of the word after the cursor, skipping the spaces and punctuation in between
+    fn word_end_after(&self) -> usize {
+        let mut graphemes = self.text[self.cursor..].grapheme_indices(true).peekable();
+        while graphemes.next_if(|(_, grapheme)| !is_word(grapheme)).is_some() {}
+        while graphemes.next_if(|(_, grapheme)| is_word(grapheme)).is_some() {}
+        graphemes.peek().map_or(self.text.len(), |(index, _)| self.cursor + index)
+    }
+
+    fn line_start(&self, position: usize) -> usize {
+        self.text[..position].rfind('\n').map_or(0, |index| index + 1)
+    }
+
+    fn line_end(&self, position: usize) -> usize {
+        self.text[position..].find('\n').map_or(self.text.len(), |index| position + index)
+    }
+
+    /// moves the cursor to the line above or below, keeping the column when the line is long enough
+    fn move_line(&mut self, direction: isize) {
+        let start = self.line_start(self.cursor);
+        let column = self.text[start..self.cursor].graphemes(true).count();
+        let target_start = if direction < 0 {
+            if start == 0 {
+                return;
+            }
+            self.line_start(start - 1)
+        } else {
+            let end = self.line_end(self.cursor);
+            if end == self.text.len() {
+                return;
+            }
+            end + 1
+        };
+        let target_end = self.line_end(target_start);
+        self.cursor = self.text[target_start..target_end]
+            .grapheme_indices(true)
+            .nth(column)
+            .map_or(target_end, |(index, _)| target_start + index);
+    }
+
+    /// the text wrapped into rows of at most `width` columns, with the row and column of the cursor.
+    /// Lines are broken at any grapheme so the cursor always lands where it is drawn.
+    pub fn wrapped(&self, width: u16) -> (Vec<String>, (u16, u16)) {
+        let width = width.max(1) as usize;
+        let mut rows = vec![String::new()];
+        let mut row_width = 0;
+        let mut cursor = (0, 0);
+        let mut offset = 0;
+        for line in self.text.split('\n') {
+            if offset > 0 {
+                rows.push(String::new());
+                row_width = 0;
+            }
+            for (index, grapheme) in line.grapheme_indices(true) {
+                let grapheme_width = grapheme.width();
+                if row_width + grapheme_width > width && row_width > 0 {
+                    rows.push(String::new());
+                    row_width = 0;
+                }
+                if offset + index == self.cursor {
+                    cursor = (rows.len() - 1, row_width);
+                }
+                if let Some(row) = rows.last_mut() {
+                    row.push_str(grapheme);
+                }
+                row_width += grapheme_width;
+            }
+            offset += line.len();
+            if offset == self.cursor {
+                // at the end of the line the cursor goes after the last grapheme, on a new row when that one is full
+                if row_width >= width {
+                    rows.push(String::new());
+                    row_width = 0;
+                }
+                cursor = (rows.len() - 1, row_width);
+            }
+            offset += 1;
+        }
+        (rows, (cursor.0 as u16, cursor.1 as u16))
+    }
+}
+
+/// whether the grapheme is part of a word for moving and deleting by words
+fn is_word(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(char::is_alphanumeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut TextInput, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        input.handle_key(KeyEvent::new(code, modifiers))
+    }
+
+    /// the text with `|` where the cursor is
+    fn shown(input: &TextInput) -> String {
+        format!("{}|{}", &input.text[..input.cursor], &input.text[input.cursor..])
+    }
+
+    #[test]
+    fn inserting() {
+        let mut single = TextInput::single_line();
+        single.insert("one\r\ntwo\tthree\u{7}");
+        assert_eq!(shown(&single), "one two    three|");
+
+        let mut multi = TextInput::multiline();
+        multi.insert("one\r\ntwo\rthree");
+        assert_eq!(shown(&multi), "one\ntwo\nthree|");
+        assert!(press(&mut multi, KeyCode::Enter, KeyModifiers::ALT));
+        assert!(!press(&mut multi, KeyCode::Enter, KeyModifiers::NONE));
+        assert!(!press(&mut single, KeyCode::Enter, KeyModifiers::ALT));
+        assert_eq!(multi.text(), "one\ntwo\nthree\n");
+    }
+
+    #[test]
+    fn graphemes() {
+        let mut input = TextInput::single_line();
+        // `e` with a combining accent and a family emoji made of several code points
+        input.set_text("cafe\u{301} 👨‍👩‍👧!");
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(shown(&input), "cafe\u{301} |👨‍👩‍👧!");
+        press(&mut input, KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(shown(&input), "cafe\u{301} |!");
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(shown(&input), "caf| !");
+    }
+
+    #[test]
+    fn words() {
+        let mut input = TextInput::single_line();
+        input.set_text("hola, buenos dias");
+        press(&mut input, KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(shown(&input), "hola, buenos |dias");
+        press(&mut input, KeyCode::Left, KeyModifiers::ALT);
+        press(&mut input, KeyCode::Left, KeyModifiers::ALT);
+        assert_eq!(shown(&input), "|hola, buenos dias");
+        press(&mut input, KeyCode::Right, KeyModifiers::CONTROL);
+        assert_eq!(shown(&input), "hola|, buenos dias");
+        press(&mut input, KeyCode::End, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(shown(&input), "hola, buenos |");
+        press(&mut input, KeyCode::Backspace, KeyModifiers::ALT);
+        assert_eq!(shown(&input), "hola, |");
+    }
+
+    #[test]
+    fn lines() {
+        let mut input = TextInput::multiline();
+        input.set_text("first line\nab\nthird line");
+        press(&mut input, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(shown(&input), "first line\nab|\nthird line");
+        // the column is kept from the short line in between
+        press(&mut input, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(shown(&input), "fi|rst line\nab\nthird line");
+        press(&mut input, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Right, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(shown(&input), "first line\na|b\nthird line");
+        press(&mut input, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(shown(&input), "first line\nab\nt|hird line");
+        press(&mut input, KeyCode::Home, KeyModifiers::CONTROL);
+        assert_eq!(shown(&input), "|first line\nab\nthird line");
+        press(&mut input, KeyCode::End, KeyModifiers::CONTROL);
+        assert_eq!(shown(&input), "first line\nab\nthird line|");
+    }
+
+    #[test]
+    fn wrapping() {
+        let rows = |rows: &[&str]| rows.iter().map(|row| row.to_string()).collect::<Vec<_>>();
+
+        let mut input = TextInput::multiline();
+        input.set_text("abcdef\ngh");
+        assert_eq!(input.wrapped(4), (rows(&["abcd", "ef", "gh"]), (2, 2)));
+        press(&mut input, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(input.wrapped(4).1, (0, 2));
+
+        // a full row puts the cursor at the end on the next row
+        input.set_text("abcd");
+        assert_eq!(input.wrapped(4), (rows(&["abcd", ""]), (1, 0)));
+
+        // wide characters take two columns
+        input.set_text("日本語");
+        assert_eq!(input.wrapped(5), (rows(&["日本", "語"]), (1, 2)));
+
+        input.clear();
+        assert_eq!(input.wrapped(0), (rows(&[""]), (0, 0)));
+    }
+}
@@ -0,0 +1,357 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+/// the colours the interface is drawn with
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ColorScheme {
+    pub title: Color,
+    pub normal: Color,
+    pub selected: Color,
+    pub highlight: Color,
+    pub warning: Color,
+    pub success: Color, // correct answers and progress
+    pub selected_box: Color,
+    pub selected_box_text: Color,
+}
+
+impl ColorScheme {
+    /// the scheme with every colour the terminal can't show replaced by the closest one it can
+    pub fn adapted(&self, support: ColorSupport) -> ColorScheme {
+        let adapt = |color| support.adapt(color);
+        ColorScheme {
+            title: adapt(self.title),
+            normal: adapt(self.normal),
+            selected: adapt(self.selected),
+            highlight: adapt(self.highlight),
+            warning: adapt(self.warning),
+            success: adapt(self.success),
+            selected_box: adapt(self.selected_box),
+            selected_box_text: adapt(self.selected_box_text),
+        }
+    }
+}
+
+/// how many colours the terminal can show
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorSupport {
+    None,      // `NO_COLOR` is set or the terminal is dumb, everything is drawn in the default colours
+    Basic,     // the 16 ANSI colours
+    Indexed,   // the 256 colour palette
+    TrueColor, // any RGB colour
+}
+
+impl ColorSupport {
+    /// guesses what the terminal supports from `NO_COLOR`, `COLORTERM` and `TERM`
+    pub fn detect() -> ColorSupport {
+        if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return ColorSupport::None;
+        }
+        if matches!(env::var("COLORTERM").as_deref(), Ok("truecolor" | "24bit")) {
+            return ColorSupport::TrueColor;
+        }
+        match env::var("TERM") {
+            Ok(term) if term == "dumb" => ColorSupport::None,
+            Ok(term) if term.contains("256color") => ColorSupport::Indexed,
+            _ => ColorSupport::Basic,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorSupport::None => "no colours",
+            ColorSupport::Basic => "16 colours",
+            ColorSupport::Indexed => "256 colours",
+            ColorSupport::TrueColor => "true colour",
+        }
+    }
+
+    fn adapt(&self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::None, _) => Color::Reset,
+            (ColorSupport::Indexed, Color::Rgb(r, g, b)) => Color::Indexed(rgb_to_indexed(r, g, b)),
+            (ColorSupport::Basic, Color::Rgb(r, g, b)) => nearest_basic(r, g, b),
+            (ColorSupport::Basic, Color::Indexed(index)) if index < 16 => BASIC_COLORS[index as usize].0,
+            (ColorSupport::Basic, Color::Indexed(index)) => {
+                let (r, g, b) = indexed_to_rgb(index);
+                nearest_basic(r, g, b)
+            }
+            (_, color) => color,
+        }
+    }
+}
+
+/// the 16 ANSI colours in palette order, with the RGB values xterm shows them with
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// the levels of each channel in the 6x6x6 colour cube of the 256 colour palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn nearest_basic(r: u8, g: u8, b: u8) -> Color {
+    BASIC_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance((r, g, b), *rgb))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// the closest colour of the cube or the grey ramp of the 256 colour palette
+fn rgb_to_indexed(r: u8, g: u8, b: u8) -> u8 {
+    let level = |value: u8| (0..6).min_by_key(|index| (CUBE_LEVELS[*index] as i32 - value as i32).abs()).unwrap_or_default();
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+    let grey_level = ((r as u32 + g as u32 + b as u32) / 3).saturating_sub(3) / 10;
+    let grey = 232 + grey_level.min(23) as usize;
+    if distance((r, g, b), indexed_to_rgb(grey as u8)) < distance((r, g, b), indexed_to_rgb(cube as u8)) {
+        grey as u8
+    } else {
+        cube as u8
+    }
+}
+
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => BASIC_COLORS[index as usize].1,
+        16..=231 => {
+            let index = index as usize - 16;
+            (CUBE_LEVELS[index / 36], CUBE_LEVELS[index / 6 % 6], CUBE_LEVELS[index % 6])
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let square = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    square(r1, r2) + square(g1, g2) + square(b1, b2)
+}
+
+/// a named colour scheme, one of the presets or read from the themes directory
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub colors: ColorScheme,
+}
+
+/// the themes that are always available, the first one is used until another one is picked
+pub const PRESETS: [&str; 5] = ["dark", "light", "solarized", "high-contrast", "monochrome"];
+
+fn preset(name: &str) -> Option<ColorScheme> {
+    let rgb = |hex: u32| Color::Rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8);
+    let scheme = match name {
+        "dark" => ColorScheme {
+            title: Color::LightYellow,
+            normal: Color::LightBlue,
+            selected: Color::White,
+            highlight: Color::Yellow,
+            warning: Color::Red,
+            success: Color::Green,
+            selected_box: Color::LightYellow,
+            selected_box_text: Color::Black,
+        },
+        "light" => ColorScheme {
+            title: Color::Blue,
+            normal: Color::Black,
+            selected: Color::Magenta,
+            highlight: rgb(0x875f00),
+            warning: Color::Red,
+            success: Color::Green,
+            selected_box: Color::Blue,
+            selected_box_text: Color::White,
+        },
+        "solarized" => ColorScheme {
+            title: rgb(0xb58900),
+            normal: rgb(0x268bd2),
+            selected: rgb(0xfdf6e3),
+            highlight: rgb(0x2aa198),
+            warning: rgb(0xdc322f),
+            success: rgb(0x859900),
+            selected_box: rgb(0xb58900),
+            selected_box_text: rgb(0x002b36),
+        },
+        "high-contrast" => ColorScheme {
+            title: Color::White,
+            normal: Color::White,
+            selected: Color::LightCyan,
+            highlight: Color::LightYellow,
+            warning: Color::LightRed,
+            success: Color::LightGreen,
+            selected_box: Color::White,
+            selected_box_text: Color::Black,
+        },
+        "monochrome" => ColorScheme {
+            title: Color::Reset,
+            normal: Color::Reset,
+            selected: Color::Reset,
+            highlight: Color::Reset,
+            warning: Color::Reset,
+            success: Color::Reset,
+            selected_box: Color::Reset,
+            selected_box_text: Color::Reset,
+        },
+        _ => return None,
+    };
+    Some(scheme)
+}
+
+/// the preset themes in the order they are listed in the theme picker
+pub fn presets() -> Vec<Theme> {
+    PRESETS.iter().filter_map(|name| Some(Theme { name: name.to_string(), colors: preset(name)? })).collect()
+}
+
+/// a colour in a theme file, a name like `lightblue`, `#rrggbb` or an index into the 256 colour palette
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorValue {
+    Index(u8),
+    Text(String),
+}
+
+/// the contents of a theme file, the colours that are left out come from `base`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    base: Option<String>,
+    title: Option<ColorValue>,
+    normal: Option<ColorValue>,
+    selected: Option<ColorValue>,
+    highlight: Option<ColorValue>,
+    warning: Option<ColorValue>,
+    success: Option<ColorValue>,
+    selected_box: Option<ColorValue>,
+    selected_box_text: Option<ColorValue>,
+}
+
+impl ThemeFile {
+    fn colors(self) -> Result<ColorScheme, String> {
+        let base = self.base.unwrap_or_else(|| String::from(PRESETS[0]));
+        let base = preset(&base).ok_or_else(|| format!("unknown base theme {base}, expected one of {}", PRESETS.join(", ")))?;
+        let color = |value: Option<ColorValue>, default: Color| match value {
+            None => Ok(default),
+            Some(ColorValue::Index(index)) => Ok(Color::Indexed(index)),
+            Some(ColorValue::Text(text)) => Color::from_str(&text).map_err(|_| format!("unknown colour {text}")),
+        };
+        Ok(ColorScheme {
+            title: color(self.title, base.title)?,
+            normal: color(self.normal, base.normal)?,
+            selected: color(self.selected, base.selected)?,
+            highlight: color(self.highlight, base.highlight)?,
+            warning: color(self.warning, base.warning)?,
+            success: color(self.success, base.success)?,
+            selected_box: color(self.selected_box, base.selected_box)?,
+            selected_box_text: color(self.selected_box_text, base.selected_box_text)?,
+        })
+    }
+}
+
+/// the presets followed by the themes in the `themes` directory of `config_dir`, one `<name>.toml` file each.
+/// A theme file named like a preset replaces it. Files that can't be read are skipped, the second list says why.
+pub fn load_themes(config_dir: &Path) -> (Vec<Theme>, Vec<String>) {
+    let mut themes = presets();
+    let mut problems = Vec::new();
+
+    let directory = config_dir.join("themes");
+    let mut paths: Vec<PathBuf> = match fs::read_dir(&directory) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => {
+            problems.push(format!("could not read {}: {err}", directory.display()));
+            Vec::new()
+        }
+    };
+    paths.retain(|path| path.extension().is_some_and(|extension| extension == "toml"));
+    paths.sort();
+    for path in paths {
+        match load_theme(&path) {
+            Ok(theme) => match themes.iter_mut().find(|existing| existing.name == theme.name) {
+                Some(existing) => existing.colors = theme.colors,
+                None => themes.push(theme),
+            },
+            Err(err) => problems.push(format!("could not read theme {}: {err}", path.display())),
+        }
+    }
+    (themes, problems)
+}
+
+/// the theme in the file at `path`, named after the file
+fn load_theme(path: &Path) -> Result<Theme, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let file: ThemeFile = toml::from_str(&contents).map_err(|err| err.to_string())?;
+    let colors = file.colors()?;
+    let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    Ok(Theme { name, colors })
+}
+
+/// the file remembering the picked theme
+#[derive(Serialize, Deserialize)]
+struct ThemeChoice {
+    theme: String,
+}
+
+/// the name of the theme picked last, `None` before a theme was ever picked
+pub fn load_choice(config_dir: &Path) -> io::Result<Option<String>> {
+    let path = config_dir.join("theme.toml");
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(io::Error::new(err.kind(), format!("could not read {}: {err}", path.display()))),
+    };
+    let choice: ThemeChoice = toml::from_str(&contents)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("could not read {}: {err}", path.display())))?;
+    Ok(Some(choice.theme))
+}
+
+pub fn save_choice(config_dir: &Path, name: &str) -> io::Result<()> {
+    fs::create_dir_all(config_dir)?;
+    let contents = toml::to_string(&ThemeChoice { theme: name.to_string() }).map_err(io::Error::other)?;
+    fs::write(config_dir.join("theme.toml"), contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn unreadable_themes_are_skipped() {
+        let config_dir = env::temp_dir().join(format!("ok-card-themes-{}", rand::random::<u64>()));
+        fs::create_dir_all(config_dir.join("themes")).unwrap();
+        fs::write(config_dir.join("themes/ocean.toml"), "base = \"dark\"\ntitle = 75\n").unwrap();
+        fs::write(config_dir.join("themes/broken.toml"), "title = ").unwrap();
+        fs::write(config_dir.join("themes/unknown.toml"), "base = \"sepia\"").unwrap();
+        fs::write(config_dir.join("theme.toml"), "theme = ").unwrap();
+
+        let (themes, problems) = load_themes(&config_dir);
+        let names: Vec<&str> = themes.iter().map(|theme| theme.name.as_str()).collect();
+        assert_eq!(names[..PRESETS.len()], PRESETS);
+        assert_eq!(names[PRESETS.len()..], ["ocean"]);
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].contains("broken.toml") && problems[1].contains("unknown.toml"), "{problems:?}");
+        assert!(load_choice(&config_dir).is_err());
+
+        fs::remove_dir_all(&config_dir).unwrap();
+    }
+}
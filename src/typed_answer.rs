@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::app::Guess;
+
+/// how strictly a typed answer is compared with the expected one, set per deck
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct AnswerOptions {
+    pub ignore_case: bool,
+    pub ignore_accents: bool,
+    pub ignore_whitespace: bool, // leading and trailing whitespace is dropped and runs of whitespace count as one space
+}
+
+impl Default for AnswerOptions {
+    fn default() -> AnswerOptions {
+        AnswerOptions { ignore_case: true, ignore_accents: false, ignore_whitespace: true }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DiffKind {
+    Same,
+    Missing, // part of the expected answer that was not typed
+    Extra, // typed but not part of the expected answer
+}
+
+/// a run of characters of the typed or the expected answer
+#[derive(Clone, PartialEq, Debug)]
+pub struct DiffPart {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+/// the outcome of comparing a typed answer with the back of the card
+#[derive(Clone, PartialEq, Debug)]
+pub struct AnswerCheck {
+    pub diff: Vec<DiffPart>,
+    pub distance: usize, // the edit distance, the number of characters that were added, removed or replaced
+    pub suggested: Guess,
+}
+
+/// compares `typed` with `expected` character by character.
+/// An exact match suggests easy, a few typos (up to one in five characters) correct and anything else incorrect.
+pub fn check_answer(typed: &str, expected: &str, options: AnswerOptions) -> AnswerCheck {
+    let typed: Vec<char> = prepare(typed, options).chars().collect();
+    let expected: Vec<char> = prepare(expected, options).chars().collect();
+    let same = |a: char, b: char| comparable(a, options) == comparable(b, options);
+
+    // distances[i][j] is the edit distance between the first i typed and the first j expected characters
+    let mut distances = vec![vec![0; expected.len() + 1]; typed.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=typed.len() {
+        for j in 1..=expected.len() {
+            let replace = distances[i - 1][j - 1] + usize::from(!same(typed[i - 1], expected[j - 1]));
+            distances[i][j] = replace.min(distances[i - 1][j] + 1).min(distances[i][j - 1] + 1);
+        }
+    }
+    let distance = distances[typed.len()][expected.len()];
+
+    // walks back from the end to find which characters line up, collecting the parts in reverse
+    let mut parts: Vec<(DiffKind, char)> = Vec::new();
+    let (mut i, mut j) = (typed.len(), expected.len());
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && same(typed[i - 1], expected[j - 1]) && distances[i][j] == distances[i - 1][j - 1] {
+            parts.push((DiffKind::Same, expected[j - 1]));
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && j > 0 && distances[i][j] == distances[i - 1][j - 1] + 1 {
+            parts.push((DiffKind::Missing, expected[j - 1]));
+            parts.push((DiffKind::Extra, typed[i - 1]));
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && distances[i][j] == distances[i - 1][j] + 1 {
+            parts.push((DiffKind::Extra, typed[i - 1]));
+            i -= 1;
+        } else {
+            parts.push((DiffKind::Missing, expected[j - 1]));
+            j -= 1;
+        }
+    }
+
+    let mut diff: Vec<DiffPart> = Vec::new();
+    for (kind, c) in parts.into_iter().rev() {
+        match diff.last_mut() {
+            Some(part) if part.kind == kind => part.text.push(c),
+            _ => diff.push(DiffPart { kind, text: c.to_string() }),
+        }
+    }
+
+    let suggested = if distance == 0 {
+        Guess::Easy
+    } else if distance * 5 <= expected.len() {
+        Guess::Correct
+    } else {
+        Guess::Incorrect
+    };
+    AnswerCheck { diff, distance, suggested }
+}
+
+/// the form both answers are compared in. Accented letters are composed into one character,
+/// so `é` typed as `e` followed by a combining accent matches a precomposed `é`, or lose their accents when they are ignored.
+fn prepare(text: &str, options: AnswerOptions) -> String {
+    let text: String = if options.ignore_accents {
+        // decomposing splits an accented letter into the letter followed by its accents
+        text.nfd().filter(|c| !is_combining_mark(*c)).collect()
+    } else {
+        text.nfc().collect()
+    };
+    if options.ignore_whitespace {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    } else {
+        text
+    }
+}
+
+/// the form of `c` two characters are compared in
+fn comparable(c: char, options: AnswerOptions) -> char {
+    if options.ignore_case {
+        c.to_lowercase().next().unwrap_or(c)
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRICT: AnswerOptions = AnswerOptions { ignore_case: false, ignore_accents: false, ignore_whitespace: false };
+
+    #[test]
+    fn suggested_grades() {
+        let cases = [
+            ("hola", "hola", AnswerOptions::default(), 0, Guess::Easy),
+            ("Hola", "hola", AnswerOptions::default(), 0, Guess::Easy),
+            ("Hola", "hola", STRICT, 1, Guess::Incorrect),
+            ("Buenos dias", "buenos dias", STRICT, 1, Guess::Correct),
+            ("  buenos   dias ", "buenos dias", AnswerOptions::default(), 0, Guess::Easy),
+            ("  buenos   dias ", "buenos dias", STRICT, 5, Guess::Incorrect),
+            ("buenas dias", "buenos dias", AnswerOptions::default(), 1, Guess::Correct),
+            ("adios", "hola", AnswerOptions::default(), 5, Guess::Incorrect),
+            ("", "hola", AnswerOptions::default(), 4, Guess::Incorrect),
+            ("cafe", "café", AnswerOptions::default(), 1, Guess::Incorrect),
+            ("cafe", "café", AnswerOptions { ignore_accents: true, ..AnswerOptions::default() }, 0, Guess::Easy),
+        ];
+        for (typed, expected, options, distance, suggested) in cases {
+            let check = check_answer(typed, expected, options);
+            assert_eq!((check.distance, check.suggested), (distance, suggested), "{typed:?} {expected:?}");
+        }
+    }
+
+    #[test]
+    fn decomposed_accents() {
+        let accents = AnswerOptions { ignore_accents: true, ..STRICT };
+        // `e` followed by a combining acute accent, like text pasted from macOS
+        assert_eq!(check_answer("cafe\u{301}", "café", STRICT).distance, 0);
+        assert_eq!(check_answer("café", "cafe\u{301}", STRICT).distance, 0);
+        assert_eq!(check_answer("cafe\u{301}", "cafe", accents).distance, 0);
+        assert_eq!(check_answer("cafe", "cafe\u{301}", accents).distance, 0);
+        assert_eq!(check_answer("cafe\u{301}", "cafe", STRICT).distance, 1);
+    }
+
+    #[test]
+    fn diff_parts() {
+        let check = check_answer("gato", "pato", STRICT);
+        let parts: Vec<(DiffKind, &str)> = check.diff.iter().map(|part| (part.kind, part.text.as_str())).collect();
+        assert_eq!(parts, vec![(DiffKind::Extra, "g"), (DiffKind::Missing, "p"), (DiffKind::Same, "ato")]);
+
+        let check = check_answer("pat", "pato", STRICT);
+        let parts: Vec<(DiffKind, &str)> = check.diff.iter().map(|part| (part.kind, part.text.as_str())).collect();
+        assert_eq!(parts, vec![(DiffKind::Same, "pat"), (DiffKind::Missing, "o")]);
+    }
+}
@@ -1,26 +1,29 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{BarChart, Block, Borders, Cell, Clear, Gauge, List, ListItem, ListState, Paragraph, Row, Sparkline, Table, TableState, Wrap},
     prelude::Alignment,
     Frame,
 };
 
 use std::rc::Rc;
 
-use crate::app::{App, CurrentScreen, CardFace};
-
-#[derive(Clone, Copy)]
-pub struct ColorScheme {
-    pub title: Color,
-    pub normal: Color,
-    pub selected: Color,
-    pub highlight: Color,
-    pub warning: Color,
-    pub selected_box: Color,
-    pub selected_box_text: Color,
-}
+use chrono::Utc;
+
+use crate::{
+    app::{note_card_kinds, App, Card, CardKind, CurrentScreen, CardFace, DeleteTarget, EditorField, Guess},
+    browser::{Browser, BrowserColumn, BulkAction},
+    cloze::{is_cloze, render_cloze},
+    csv_import::{CsvImport, ImportTarget, SkipReason},
+    deck_tree,
+    export::ExportFormat,
+    keymap::{Action, KeyContext, ANSWER_OPTION_HINTS, LEARNING_HINTS},
+    stats::{format_duration, STATS_DAYS},
+    text_input::TextInput,
+    theme::ColorScheme,
+    typed_answer::{AnswerCheck, AnswerOptions, DiffKind},
+};
 
 pub fn ui(f: &mut Frame, app: &App) {
     // Create the layout sections.
@@ -34,50 +37,84 @@ pub fn ui(f: &mut Frame, app: &App) {
         ])
         .split(f.size());
         
-    //initialize ColorScheme
-    let color_scheme = ColorScheme {
-        title: Color::LightYellow, 
-        normal: Color::LightBlue,
-        selected: Color::White,
-        highlight: Color::Yellow,
-        warning: Color::Red,
-        selected_box: Color::LightYellow,
-        selected_box_text: Color::Black,
-    };
+    let color_scheme = app.color_scheme();
 
     //display the title
-    display_title(f, chunks.clone(), color_scheme.clone());
+    display_title(f, chunks.clone(), color_scheme);
 
     //display decks
     if app.display_decks {
-        display_decks(app, f, chunks.clone(), color_scheme.clone());
+        display_decks(app, f, chunks.clone(), color_scheme);
     }
     
-    //display the cards in the selected deck
-    if let CurrentScreen::ViewingDeck = app.current_screen {
-        display_cards_of_selected_deck(app, f, chunks.clone(), color_scheme.clone());
+    //display the cards in the selected deck, the search overlay keeps them visible when it was opened from a deck
+    match app.current_screen {
+        CurrentScreen::ViewingDeck | CurrentScreen::FilteringTags => {
+            display_cards_of_selected_deck(app, f, chunks.clone(), color_scheme);
+        }
+        CurrentScreen::Searching if !app.display_decks => {
+            display_cards_of_selected_deck(app, f, chunks.clone(), color_scheme);
+        }
+        _ => {}
+    }
+
+    // the browser stays visible under the editor and delete popups opened from it
+    if let Some(browser) = &app.browser {
+        display_browser(app, browser, f, chunks.clone(), color_scheme);
     }
 
     //display the footer
-    display_footer(app, f, chunks.clone(), color_scheme.clone());
+    display_footer(app, f, chunks.clone(), color_scheme);
 
     //display the adding of the deck
     if app.adding_deck {
-        display_add_deck(app, f, chunks.clone(), color_scheme.clone());
+        display_add_deck(app, f, chunks.clone(), color_scheme);
     }
 
     // display the cards being added/edited
     if let Some(editing) = &app.card_currently_editing {
-        display_add_card(app, f, chunks.clone(), color_scheme.clone(), *editing);
+        display_add_card(app, f, chunks.clone(), color_scheme, *editing);
     }
     
     //display the cards being learned
     if let CurrentScreen::LearningMode = app.current_screen {
-        display_learning_mode(app, f, chunks.clone(), color_scheme.clone());
+        display_learning_mode(app, f, chunks.clone(), color_scheme);
+    }
+
+    if let CurrentScreen::EnteringImportPath = app.current_screen {
+        display_import_path(app, f, color_scheme);
+    }
+
+    if let (CurrentScreen::ImportPreview, Some(import)) = (app.current_screen, &app.csv_import) {
+        display_import_preview(app, import, f, chunks.clone(), color_scheme);
+    }
+
+    if let Some(target) = app.pending_delete {
+        display_confirm_delete(app, f, color_scheme, target);
+    }
+
+    if let CurrentScreen::FilteringTags = app.current_screen {
+        display_tag_filter(app, f, color_scheme);
+    }
+
+    if let CurrentScreen::Searching = app.current_screen {
+        display_search(app, f, color_scheme);
+    }
+
+    if let CurrentScreen::EnteringQuery = app.current_screen {
+        display_custom_study_query(app, f, color_scheme);
+    }
+
+    if let CurrentScreen::Statistics = app.current_screen {
+        display_statistics(app, f, chunks.clone(), color_scheme);
+    }
+
+    if let CurrentScreen::PickingTheme = app.current_screen {
+        display_theme_picker(app, f, color_scheme);
     }
 
     if let CurrentScreen::Exiting = app.current_screen {
-        display_exit_screen(f, color_scheme.clone());
+        display_exit_screen(app, f, color_scheme);
     }
 }
 
@@ -121,27 +158,47 @@ fn display_title(f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: ColorScheme) {
 fn display_decks(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: ColorScheme) {
     let mut list_items = Vec::<ListItem>::new();
     
-    for (index, deck) in app.decks.iter().enumerate() {
-        let mut format = format!("{} - {} ", index+1, deck.name);
+    let now = Utc::now();
+    let rows = deck_tree::tree_rows(&app.decks);
+    // the counts line up after the longest name
+    let name_width = rows
+        .iter()
+        .map(|row| row.depth * 2 + 2 + deck_tree::short_name(&app.decks[row.index].name).chars().count())
+        .max()
+        .unwrap_or_default();
+    for row in rows {
+        let deck = &app.decks[row.index];
+        let counts = deck_tree::deck_counts(&app.decks, row.index, now);
+        // nested decks are indented under their parent, which shows whether they are collapsed
+        let marker = match (row.has_children, deck.collapsed) {
+            (false, _) => " ",
+            (true, false) => "▾",
+            (true, true) => "▸",
+        };
+        let name = format!("{}{} {}", "  ".repeat(row.depth), marker, deck_tree::short_name(&deck.name));
+        let mut format = format!("   {:width$}   ", name, width = name_width);
         let mut style = Style::default().fg(color_scheme.normal);
-        if Some(index) == app.selected_index {
-            format = format!("-> {} - {} <-", index+1, deck.name);
+        if Some(row.index) == app.selected_index {
+            format = format!("-> {:width$} <-", name, width = name_width);
             style = Style::default().fg(color_scheme.selected);
         }
-        list_items.push(ListItem::new(Line::from(Span::styled(
-            format,
-            style,
-        ))));
+        list_items.push(ListItem::new(Line::from(vec![
+            Span::styled(format, style),
+            Span::styled(
+                format!("  {} new / {} due / {} total", counts.new, counts.due, counts.total),
+                Style::default().fg(color_scheme.highlight),
+            ),
+        ])));
     }
 
-    let deck_title = if app.decks.len() > 0 {
+    let deck_title = if !app.decks.is_empty() {
         Paragraph::new(Text::styled(
             "Decks:",
             Style::default().fg(color_scheme.title),
         ))
     } else {
         Paragraph::new(Text::styled(
-            "Press (a) to add a deck",
+            format!("Press ({}) to add a deck", app.keymap.key_name(Action::Add)),
             Style::default().fg(color_scheme.title),
         ))
     };
@@ -154,28 +211,44 @@ fn display_decks(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: Col
 }
 
 fn display_cards_of_selected_deck(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: ColorScheme) {
+    let Some(deck) = app.selected_index.and_then(|index| app.decks.get(index)) else {
+        return;
+    };
     let mut list_items = Vec::<ListItem>::new();
-    for (index, card) in app.decks[app.selected_index.unwrap_or_default()].cards.iter().enumerate() { //c
-        let mut format = format!("{} - {}", index+1, card.front);
+    let cards = &deck.cards;
+    for index in app.visible_cards() { //c
+        let card = &cards[index];
+        let mut format = format!("{} - {}", index+1, card.question());
         let mut style = Style::default().fg(color_scheme.normal);
         if Some(index) == app.selected_card_index {
-            format = format!("{} - {} <-", index+1, card.front);
+            format = format!("{} - {} <-", index+1, card.question());
             style = Style::default().fg(color_scheme.selected);
         }
-        list_items.push(ListItem::new(Line::from(Span::styled(
-            format,
-            style,
-        ))));
+        let mut spans = vec![Span::styled(format, style)];
+        if !card.tags.is_empty() {
+            let tags = card.tags.iter().map(|tag| format!("#{tag}")).collect::<Vec<_>>().join(" ");
+            spans.push(Span::styled(format!("  {tags}"), Style::default().fg(color_scheme.highlight)));
+        }
+        list_items.push(ListItem::new(Line::from(spans)));
     }
 
-    let cards_paragraph_heading = if app.decks[app.selected_index.unwrap_or_default()].cards.len() > 0 {
-        let text_title_display = format!("Cards for deck {}", &app.decks[app.selected_index.unwrap_or_default()].name);
+    let cards_paragraph_heading = if !deck.cards.is_empty() {
+        let mut text_title_display =
+            format!("Cards for deck {} ({}, {})", deck.name, deck.scheduler.scheduler().name(), deck.review_mode.name());
+        if !app.tag_filter.is_empty() {
+            let tags = app.tag_filter.iter().cloned().collect::<Vec<_>>().join(", ");
+            text_title_display = format!("{text_title_display} - tagged {tags}");
+        }
         Paragraph::new(Text::styled(
             text_title_display,
             Style::default().fg(color_scheme.title),
         ))
     } else {
-        let text_title_display = format!("Press (a) to add a card to deck {}", &app.decks[app.selected_index.unwrap_or_default()].name);
+        let text_title_display = format!(
+            "Press ({}) to add a card to deck {}",
+            app.keymap.key_name(Action::Add),
+            &deck.name
+        );
         Paragraph::new(Text::styled(
             text_title_display,
             Style::default().fg(color_scheme.title),
@@ -183,12 +256,275 @@ fn display_cards_of_selected_deck(app: &App, f: &mut Frame, chunks: Rc<[Rect]>,
     };
 
     let list_of_cards = List::new(list_items);
+    // the list scrolls to keep the selected card visible in long decks
+    let selected_position = app.visible_cards().iter().position(|index| Some(*index) == app.selected_card_index);
+    let mut state = ListState::default().with_selected(selected_position);
     if !app.display_decks {
         f.render_widget(cards_paragraph_heading, chunks[1]);
-        f.render_widget(list_of_cards, chunks[2]);
+        f.render_stateful_widget(list_of_cards, chunks[2], &mut state);
+    }
+}
+
+/// the search query with the matching cards of every deck below it
+fn display_search(app: &App, f: &mut Frame, color_scheme: ColorScheme) {
+    let area = centered_rect(80, 80, f.size());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Block::default()
+            .title("Search all decks")
+            .style(Style::default().bg(color_scheme.selected_box_text).fg(color_scheme.selected_box)),
+        area,
+    );
+
+    let search_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(3), Constraint::Min(1)])
+        .split(area);
+
+    let query_block = Block::default().title("Front, back or tags").borders(Borders::ALL);
+    display_text_input(f, &app.search_input, query_block, search_chunks[0], true);
+
+    let result_items: Vec<ListItem> = app
+        .search_results
+        .iter()
+        .map(|result| {
+            let deck = &app.decks[result.deck_index];
+            let card = &deck.cards[result.card_index];
+            let mut spans = vec![
+                Span::styled(format!("{}  ", deck.name), Style::default().fg(color_scheme.title)),
+                // cloze and reverse cards are shown the way they are asked, like in the browser preview
+                Span::raw(format!("{} → {}", card.question().replace('\n', " "), card.answer().replace('\n', " "))),
+            ];
+            if !card.tags.is_empty() {
+                let tags = card.tags.iter().map(|tag| format!("#{tag}")).collect::<Vec<_>>().join(" ");
+                spans.push(Span::styled(format!("  {tags}"), Style::default().fg(color_scheme.highlight)));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let title = match app.search_results.len() {
+        0 if app.search_input.text().trim().is_empty() => String::from("Type to search"),
+        0 => String::from("No cards found"),
+        1 => String::from("1 card"),
+        count => format!("{count} cards"),
+    };
+    let results = List::new(result_items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().fg(color_scheme.selected))
+        .highlight_symbol("-> ");
+    // the list scrolls to keep the selected result visible
+    let mut state = ListState::default().with_selected((!app.search_results.is_empty()).then_some(app.search_index));
+    f.render_stateful_widget(results, search_chunks[1], &mut state);
+}
+
+/// the query picking the cards of a custom study session, with a caret under the mistake when it can't be parsed
+fn display_custom_study_query(app: &App, f: &mut Frame, color_scheme: ColorScheme) {
+    let area = centered_rect(70, 50, f.size());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Block::default()
+            .title("Custom study: learn the cards matching a query")
+            .style(Style::default().bg(color_scheme.selected_box_text).fg(color_scheme.selected_box)),
+        area,
+    );
+
+    let query_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(3), Constraint::Length(1), Constraint::Min(1)])
+        .split(area);
+
+    display_text_input(f, &app.query_input, Block::default().title("Query").borders(Borders::ALL), query_chunks[0], true);
+
+    let (outcome, style) = match &app.query_error {
+        Some(error) => {
+            // the caret lines up with the query inside the border
+            let caret = format!("{}^", " ".repeat(error.position + 1));
+            f.render_widget(Paragraph::new(Text::styled(caret, Style::default().fg(color_scheme.warning))), query_chunks[1]);
+            (error.message.clone(), Style::default().fg(color_scheme.warning))
+        }
+        None => {
+            let outcome = match app.query_matches.len() {
+                0 => String::from("No cards match"),
+                1 => String::from("1 card matches"),
+                count => format!("{count} cards match"),
+            };
+            (outcome, Style::default().fg(color_scheme.title))
+        }
+    };
+    let help = Text::from(vec![
+        Line::from(Span::styled(outcome, style)),
+        Line::from(""),
+        Line::from("deck:name  tag:name  front:text  back:text  plain words"),
+        Line::from("is:due|new|review|suspended  added:days  rated:days[:incorrect|correct|easy]"),
+        Line::from("prop:ivl|due|ease|lapses|reps>number"),
+        Line::from("-term leaves cards out, a or b needs either, (brackets) group"),
+        Line::from("* matches any text"),
+    ]);
+    f.render_widget(Paragraph::new(help).wrap(Wrap { trim: false }), query_chunks[2]);
+}
+
+/// the table of the cards matching the browser query, with the card under the cursor previewed on the right
+fn display_browser(app: &App, browser: &Browser, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: ColorScheme) {
+    let mut heading = match browser.rows.len() {
+        1 => String::from("1 card"),
+        count => format!("{count} cards"),
+    };
+    if !browser.marked.is_empty() {
+        heading = format!("{heading}, {} marked", browser.marked.len());
+    }
+    let order = if browser.sort_descending { "descending" } else { "ascending" };
+    heading = format!("{heading} - sorted by {} {order}", browser.sort_column.name().to_lowercase());
+    f.render_widget(Clear, chunks[1]);
+    f.render_widget(Paragraph::new(Text::styled(heading, Style::default().fg(color_scheme.title))), chunks[1]);
+
+    let caret_height = if browser.query_error.is_some() { 1 } else { 0 };
+    let browser_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Length(caret_height), Constraint::Min(3)])
+        .split(chunks[2]);
+    f.render_widget(Clear, chunks[2]);
+
+    let (query_title, query_style) = match &browser.query_error {
+        Some(error) => (error.message.clone(), Style::default().fg(color_scheme.warning)),
+        None if browser.editing_query => (String::from("Query"), Style::default().fg(color_scheme.selected)),
+        None => (String::from("Query (/)"), Style::default().fg(color_scheme.normal)),
+    };
+    let query_block = Block::default().title(query_title).borders(Borders::ALL).style(query_style);
+    display_text_input(f, &browser.query_input, query_block, browser_chunks[0], browser.editing_query && browser.action.is_none());
+    if let Some(error) = &browser.query_error {
+        let caret = format!("{}^", " ".repeat(error.position + 1));
+        f.render_widget(Paragraph::new(Text::styled(caret, Style::default().fg(color_scheme.warning))), browser_chunks[1]);
+    }
+
+    let table_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+        .split(browser_chunks[2]);
+
+    let header = Row::new(BrowserColumn::ALL.iter().enumerate().map(|(index, column)| {
+        let arrow = match (browser.sort_column == *column, browser.sort_descending) {
+            (false, _) => "",
+            (true, false) => " ▲",
+            (true, true) => " ▼",
+        };
+        Cell::from(format!("{} {}{arrow}", index + 1, column.name()))
+    }))
+    .style(Style::default().fg(color_scheme.title).add_modifier(Modifier::BOLD));
+
+    let rows: Vec<Row> = browser
+        .rows
+        .iter()
+        .map(|(deck_index, card_index)| {
+            let deck = &app.decks[*deck_index];
+            let card = &deck.cards[*card_index];
+            let mut style = Style::default().fg(color_scheme.normal);
+            // reversed so marked rows stand out without colours too
+            if browser.is_marked(&app.decks, (*deck_index, *card_index)) {
+                style = style.fg(color_scheme.highlight).add_modifier(Modifier::REVERSED);
+            }
+            if card.suspended {
+                style = style.add_modifier(Modifier::DIM);
+            }
+            Row::new(BrowserColumn::ALL.iter().map(|column| Cell::from(column.cell(deck, card)))).style(style)
+        })
+        .collect();
+    // the numbers get the width of their header, the text columns share what is left
+    let fixed_widths = [10, 10, 6, 8, 12];
+    let borders_and_spacing = 2 + 3 + BrowserColumn::ALL.len() as u16 - 1;
+    let text_width = table_chunks[0].width.saturating_sub(fixed_widths.iter().sum::<u16>() + borders_and_spacing);
+    let widths: Vec<Constraint> = [30, 30, 22, 18]
+        .iter()
+        .map(|share| Constraint::Length((text_width * share / 100).max(4)))
+        .chain(fixed_widths.iter().map(|width| Constraint::Length(*width)))
+        .collect();
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL))
+        .highlight_style(Style::default().fg(color_scheme.selected).add_modifier(Modifier::BOLD))
+        .highlight_symbol("-> ");
+    // the table scrolls to keep the cursor visible
+    let mut state = TableState::default().with_selected((!browser.rows.is_empty()).then_some(browser.selected));
+    f.render_stateful_widget(table, table_chunks[0], &mut state);
+
+    let preview = match browser.selected_row() {
+        Some((deck_index, card_index)) => {
+            let deck = &app.decks[deck_index];
+            let card = &deck.cards[card_index];
+            let reviews = app.review_log.iter().filter(|entry| entry.card_id == card.id).count();
+            let detail = |label: &str, value: String| {
+                Line::from(vec![Span::styled(format!("{label}: "), Style::default().fg(color_scheme.title)), Span::raw(value)])
+            };
+            let mut lines: Vec<Line> = card
+                .question()
+                .lines()
+                .map(|line| Line::from(Span::styled(line.to_string(), Style::default().add_modifier(Modifier::BOLD))))
+                .collect();
+            lines.push(Line::from(""));
+            lines.extend(card.answer().lines().map(|line| Line::from(line.to_string())));
+            lines.push(Line::from(""));
+            lines.push(detail("Deck", deck.name.clone()));
+            for column in [BrowserColumn::Tags, BrowserColumn::Due, BrowserColumn::Interval, BrowserColumn::Ease, BrowserColumn::Lapses, BrowserColumn::LastGrade] {
+                lines.push(detail(column.name(), column.cell(deck, card)));
+            }
+            lines.push(detail("Reviews", reviews.to_string()));
+            Text::from(lines)
+        }
+        None => Text::from("No cards match the query"),
+    };
+    let preview_paragraph = Paragraph::new(preview)
+        .block(Block::default().title("Preview").borders(Borders::ALL))
+        .style(Style::default().fg(color_scheme.normal))
+        .wrap(Wrap { trim: false });
+    f.render_widget(preview_paragraph, table_chunks[1]);
+
+    if let Some(action) = browser.action {
+        let title = match action {
+            BulkAction::Move => "Move the marked cards to the deck",
+            BulkAction::Tag => "Tags to add, -tag removes a tag",
+        };
+        let area = centered_rect(50, 20, f.size());
+        f.render_widget(Clear, area);
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .style(Style::default().bg(color_scheme.selected_box_text).fg(color_scheme.selected_box));
+        display_text_input(f, &browser.action_input, block, area, true);
     }
 }
 
+/// the tags of the selected deck, the ones the cards are filtered by are ticked
+fn display_tag_filter(app: &App, f: &mut Frame, color_scheme: ColorScheme) {
+    let tags = app.deck_tags();
+    let area = centered_rect(40, 50, f.size());
+    f.render_widget(Clear, area);
+
+    let tag_items: Vec<ListItem> = if tags.is_empty() {
+        vec![ListItem::new("This deck has no tags, add them in the card editor")]
+    } else {
+        tags.iter()
+            .enumerate()
+            .map(|(index, (tag, count))| {
+                let checkbox = if app.tag_filter.contains(tag) { "[x]" } else { "[ ]" };
+                let style = if index == app.tag_filter_index {
+                    Style::default().fg(color_scheme.selected)
+                } else {
+                    Style::default().fg(color_scheme.normal)
+                };
+                ListItem::new(Line::from(Span::styled(format!("{checkbox} {tag} ({count})"), style)))
+            })
+            .collect()
+    };
+    let tag_list = List::new(tag_items).block(
+        Block::default()
+            .title("Show cards tagged with")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(color_scheme.selected_box_text).fg(color_scheme.selected_box)),
+    );
+    f.render_widget(tag_list, area);
+}
+
 fn display_footer(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: ColorScheme) {
     let current_navigation_text = vec![
         // The first half of the text
@@ -205,9 +541,33 @@ fn display_footer(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: Co
             CurrentScreen::EditingCard => {
                 Span::styled("Editing Card", Style::default().fg(color_scheme.title))
             }
+            CurrentScreen::ConfirmingDelete => {
+                Span::styled("Deleting", Style::default().fg(color_scheme.warning))
+            }
+            CurrentScreen::EnteringImportPath | CurrentScreen::ImportPreview => {
+                Span::styled("Importing", Style::default().fg(color_scheme.title))
+            }
+            CurrentScreen::FilteringTags => {
+                Span::styled("Filtering Tags", Style::default().fg(color_scheme.title))
+            }
+            CurrentScreen::Searching => {
+                Span::styled("Searching", Style::default().fg(color_scheme.title))
+            }
+            CurrentScreen::EnteringQuery => {
+                Span::styled("Custom Study", Style::default().fg(color_scheme.title))
+            }
+            CurrentScreen::Browsing => {
+                Span::styled("Browsing", Style::default().fg(color_scheme.title))
+            }
             CurrentScreen::LearningMode => {
                 Span::styled("Learning Mode", Style::default().fg(color_scheme.title))
             }
+            CurrentScreen::Statistics => {
+                Span::styled("Statistics", Style::default().fg(color_scheme.title))
+            }
+            CurrentScreen::PickingTheme => {
+                Span::styled("Themes", Style::default().fg(color_scheme.title))
+            }
             CurrentScreen::Exiting => {
                 Span::styled("Exiting", Style::default().fg(color_scheme.warning))
             }
@@ -216,8 +576,9 @@ fn display_footer(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: Co
         // A white divider bar to separate the two sections
         Span::styled(" | ", Style::default().fg(color_scheme.selected)),
         // The final section of the text, with hints on what the user is editing
-        {
-            Span::styled("Editing", Style::default().fg(color_scheme.title))
+        match &app.status_message {
+            Some(message) => Span::styled(message.clone(), Style::default().fg(color_scheme.highlight)),
+            None => Span::styled("Editing", Style::default().fg(color_scheme.title)),
         },
     ];
 
@@ -227,34 +588,112 @@ fn display_footer(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: Co
     let current_keys_hint = {
         match app.current_screen {
             CurrentScreen::Main => Span::styled(
-                "(q) quit / (a) add deck",
+                app.keymap.hints(KeyContext::DeckList.actions()),
                 Style::default().fg(color_scheme.title),
             ),
             CurrentScreen::AddingDeck => Span::styled(
-                "(ESC) cancel/ (ENTER) complete",
+                "(ESC) cancel/ (ENTER) complete/ Parent::Child to nest decks",
                 Style::default().fg(color_scheme.title),
             ),
             CurrentScreen::ViewingDeck => Span::styled(
-                "(q) back/ (a) add card",
+                app.keymap.hints(KeyContext::Deck.actions()),
                 Style::default().fg(color_scheme.title),
             ),
             CurrentScreen::EditingCard => Span::styled(
-                "(ESC) cancel/ (ENTER) complete",
+                "(ESC) cancel/ (ENTER) complete/ (ALT-ENTER) new line/ (TAB) switch side/ (CTRL-D) direction/ (CTRL-E) $EDITOR/ {{c1::text}} for cloze",
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::ConfirmingDelete => Span::styled(
+                "(y) delete/ (n) cancel",
+                Style::default().fg(color_scheme.warning),
+            ),
+            CurrentScreen::EnteringImportPath => Span::styled(
+                "(ESC) cancel/ (ENTER) open",
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::ImportPreview => Span::styled(
+                "(ESC) cancel/ (ENTER) import/ (d) delimiter/ (h) header/ (f) front/ (b) back/ (t) tags/ (n) deck",
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::Searching => Span::styled(
+                "(ESC) cancel/ (ENTER) go to card/ (UP/DOWN) select",
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::EnteringQuery => Span::styled(
+                "(ESC) cancel/ (ENTER) study the matching cards",
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::Browsing if app.browser_action().is_some() => Span::styled(
+                "(ESC) cancel/ (ENTER) apply to the marked cards",
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::Browsing if app.editing_browser_query() => Span::styled(
+                "(ESC/ENTER) done/ deck:name tag:name is:due ...",
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::Browsing => Span::styled(
+                format!("{}/ (1-9) sort", app.keymap.hints(KeyContext::Browser.actions())),
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::FilteringTags => Span::styled(
+                app.keymap.hints(KeyContext::TagFilter.actions()),
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::LearningMode if app.typing_answer() => Span::styled(
+                "(ESC) back/ (ENTER) check answer",
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::LearningMode if app.choosing_answer() => Span::styled(
+                format!(
+                    "{}/ (1-{}) pick the answer",
+                    app.keymap.hints(LEARNING_HINTS),
+                    app.multiple_choice.as_ref().map_or(0, |multiple_choice| multiple_choice.choices.len())
+                ),
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::LearningMode if app.answer_check().is_some() => Span::styled(
+                format!(
+                    "{}/ (ENTER) accept suggested grade/ {}",
+                    app.keymap.hints(LEARNING_HINTS),
+                    app.keymap.hints(ANSWER_OPTION_HINTS)
+                ),
                 Style::default().fg(color_scheme.title),
             ),
             CurrentScreen::LearningMode => Span::styled(
-                "(q) back",
+                app.keymap.hints(LEARNING_HINTS),
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::Statistics => Span::styled(
+                app.keymap.hints(KeyContext::Statistics.actions()),
+                Style::default().fg(color_scheme.title),
+            ),
+            CurrentScreen::PickingTheme => Span::styled(
+                app.keymap.hints(KeyContext::ThemePicker.actions()),
                 Style::default().fg(color_scheme.title),
             ),
             CurrentScreen::Exiting => Span::styled(
-                "(q) quit",
+                "(1-5) export and quit/ (n) quit",
                 Style::default().fg(color_scheme.warning),
             ),
         }
     };
 
+    // what undo and redo would change, in front of the other keys so it isn't cut off
+    let mut undo_hint = String::new();
+    if app.key_context().is_some_and(|context| context.contains(Action::Undo)) {
+        if let Some(description) = app.history.next_undo() {
+            undo_hint.push_str(&format!("({}) undo {description}/ ", app.keymap.key_name(Action::Undo)));
+        }
+        if let Some(description) = app.history.next_redo() {
+            undo_hint.push_str(&format!("({}) redo {description}/ ", app.keymap.key_name(Action::Redo)));
+        }
+    }
+
     //display the footer
-    let key_notes_footer = Paragraph::new(Line::from(current_keys_hint))
+    let key_notes_footer = Paragraph::new(Line::from(vec![
+        Span::styled(undo_hint, Style::default().fg(color_scheme.highlight)),
+        current_keys_hint,
+    ]))
         .block(Block::default().borders(Borders::ALL));
 
     let footer_chunks = Layout::default()
@@ -266,9 +705,10 @@ fn display_footer(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: Co
     f.render_widget(key_notes_footer, footer_chunks[1]);
 }
 
-fn display_add_deck(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: ColorScheme) {
+fn display_add_deck(app: &App, f: &mut Frame, _chunks: Rc<[Rect]>, color_scheme: ColorScheme) {
+    let title = if app.deck_being_renamed.is_some() { "Enter new name of deck:" } else { "Enter name of new deck:" };
     let popup_block = Block::default()
-        .title("Enter name of new deck:")
+        .title(title)
         .borders(Borders::NONE)
         .style(Style::default().bg(color_scheme.selected_box_text).fg(color_scheme.selected_box));
 
@@ -287,49 +727,237 @@ fn display_add_deck(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme:
     let _active_style =
         Style::default().bg(color_scheme.selected_box).fg(color_scheme.selected_box_text);
 
-    let name_text = Paragraph::new(app.name_input.clone()).block(name_block);
-    f.render_widget(name_text, popup_chunks[0]);
+    display_text_input(f, &app.name_input, name_block, popup_chunks[0], true);
+}
+
+/// draws a text field inside `block`, scrolled to keep the cursor visible, and shows the terminal cursor in it when it is `active`
+fn display_text_input(f: &mut Frame, input: &TextInput, block: Block, area: Rect, active: bool) {
+    let inner = block.inner(area);
+    let (rows, (row, column)) = input.wrapped(inner.width);
+    let scroll = row.saturating_sub(inner.height.saturating_sub(1));
+    let lines: Vec<Line> = rows.into_iter().map(Line::from).collect();
+    f.render_widget(Paragraph::new(lines).block(block).scroll((scroll, 0)), area);
+    if active && inner.width > 0 && inner.height > 0 {
+        f.set_cursor(inner.x + column.min(inner.width - 1), inner.y + row - scroll);
+    }
 }
 
-fn display_add_card(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: ColorScheme, editing: CardFace) {
+fn display_add_card(app: &App, f: &mut Frame, _chunks: Rc<[Rect]>, color_scheme: ColorScheme, editing: EditorField) {
+    let title = if app.card_being_edited.is_some() { "Edit card" } else { "Enter card information" };
     let popup_block = Block::default()
-        .title("Enter card information")
+        .title(title)
         .borders(Borders::NONE)
         .style(Style::default().bg(color_scheme.selected_box_text).fg(color_scheme.selected_box));
 
-    let area = centered_rect(60, 25, f.size());
+    // cloze text generates several cards, which are previewed below the fields
+    let cloze_cards = note_card_kinds(app.front_input.text(), app.card_direction);
+    let is_cloze = is_cloze(app.front_input.text());
+
+    let area = if is_cloze { centered_rect(60, 60, f.size()) } else { centered_rect(60, 50, f.size()) };
+    f.render_widget(Clear, area);
     f.render_widget(popup_block, area);
 
+    let constraints = if is_cloze {
+        vec![Constraint::Min(3), Constraint::Min(3), Constraint::Length(3), Constraint::Length(cloze_cards.len() as u16 + 2)]
+    } else {
+        vec![Constraint::Min(3), Constraint::Min(3), Constraint::Length(3), Constraint::Length(1)]
+    };
     let popup_chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
-        .constraints([
-            Constraint::Percentage(50),
-            Constraint::Percentage(50),
-        ])
+        .constraints(constraints)
         .split(area);
 
-    let mut card_front_block = Block::default().title("Card Front").borders(Borders::ALL);
+    let (front_title, back_title) = if is_cloze { ("Cloze text", "Extra") } else { ("Card Front", "Card back") };
+    let mut card_front_block = Block::default().title(front_title).borders(Borders::ALL);
     let mut card_back_block =
-        Block::default().title("Card back").borders(Borders::ALL);
+        Block::default().title(back_title).borders(Borders::ALL);
+    let mut card_tags_block = Block::default().title("Tags (separated by spaces)").borders(Borders::ALL);
 
     let active_style =
         Style::default().bg(color_scheme.selected_box).fg(color_scheme.selected_box_text);
 
     match editing {
-        CardFace::CardFront => card_front_block = card_front_block.style(active_style),
-        CardFace::CardBack => {
+        EditorField::Front => card_front_block = card_front_block.style(active_style),
+        EditorField::Back => {
             card_back_block = card_back_block.style(active_style)
         }
+        EditorField::Tags => card_tags_block = card_tags_block.style(active_style),
     };
     
 
-    let card_front_text = Paragraph::new(app.front_input.clone()).block(card_front_block);
-    f.render_widget(card_front_text, popup_chunks[0]);
+    display_text_input(f, &app.front_input, card_front_block, popup_chunks[0], editing == EditorField::Front);
+    display_text_input(f, &app.back_input, card_back_block, popup_chunks[1], editing == EditorField::Back);
+    display_text_input(f, &app.tags_input, card_tags_block, popup_chunks[2], editing == EditorField::Tags);
 
-    let card_back_text =
-        Paragraph::new(app.back_input.clone()).block(card_back_block);
-    f.render_widget(card_back_text, popup_chunks[1]);
+    if is_cloze {
+        let preview_items: Vec<ListItem> = cloze_cards
+            .iter()
+            .filter_map(|kind| match kind {
+                CardKind::Cloze(index) => Some(ListItem::new(Line::from(format!(
+                    "c{}: {}",
+                    index,
+                    render_cloze(app.front_input.text(), *index, false)
+                )))),
+                CardKind::Basic | CardKind::Reverse => None,
+            })
+            .collect();
+        let preview = List::new(preview_items)
+            .block(Block::default().title(format!("Generates {} cards", cloze_cards.len())).borders(Borders::ALL));
+        f.render_widget(preview, popup_chunks[3]);
+    } else {
+        let direction = Paragraph::new(format!("Asked {} (CTRL-D to change)", app.card_direction.name()));
+        f.render_widget(direction, popup_chunks[3]);
+    }
+}
+
+fn display_confirm_delete(app: &App, f: &mut Frame, color_scheme: ColorScheme, target: DeleteTarget) {
+    let question = match target {
+        DeleteTarget::Deck(index) => app.decks.get(index).map(|deck| {
+            let family = app.deck_family(index);
+            let card_count: usize = family.iter().map(|deck| deck.cards.len()).sum();
+            match family.len() - 1 {
+                0 => format!("Delete deck {} and its {} cards? (y/n)", deck.name, card_count),
+                nested => format!("Delete deck {}, its {} nested decks and their {} cards? (y/n)", deck.name, nested, card_count),
+            }
+        }),
+        DeleteTarget::Card(card_index) => app
+            .selected_index
+            .and_then(|index| app.decks.get(index))
+            .and_then(|deck| deck.cards.get(card_index))
+            .map(|card| format!("Delete card {}? (y/n)", card.question())),
+        DeleteTarget::BrowserCards => match app.browser_targets().len() {
+            1 => Some(String::from("Delete the marked card? (y/n)")),
+            count => Some(format!("Delete the {count} marked cards? (y/n)")),
+        },
+    };
+
+    let popup_block = Block::default()
+        .title("Y/N")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(color_scheme.selected_box_text).fg(color_scheme.selected_box));
+
+    let question_paragraph = Paragraph::new(Text::styled(
+        question.unwrap_or_default(),
+        Style::default().fg(color_scheme.warning),
+    ))
+    .block(popup_block)
+    .wrap(Wrap { trim: false });
+
+    let area = centered_rect(60, 25, f.size());
+    f.render_widget(Clear, area);
+    f.render_widget(question_paragraph, area);
+}
+
+fn display_import_path(app: &App, f: &mut Frame, color_scheme: ColorScheme) {
+    let popup_block = Block::default()
+        .title("Enter path of the CSV/TSV file or Anki package to import:")
+        .borders(Borders::NONE)
+        .style(Style::default().bg(color_scheme.selected_box_text).fg(color_scheme.selected_box));
+
+    let area = centered_rect(60, 25, f.size());
+    f.render_widget(Clear, area);
+    f.render_widget(popup_block, area);
+
+    let popup_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(3), Constraint::Min(1)])
+        .split(area);
+
+    display_text_input(f, &app.path_input, Block::default().title("Path").borders(Borders::ALL), popup_chunks[0], true);
+
+    if let Some(error) = &app.import_error {
+        let error_text = Paragraph::new(Text::styled(error.clone(), Style::default().fg(color_scheme.warning)))
+            .wrap(Wrap { trim: false });
+        f.render_widget(error_text, popup_chunks[1]);
+    }
+}
+
+fn display_import_preview(app: &App, import: &CsvImport, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: ColorScheme) {
+    let heading = format!("Importing {}", import.path.display());
+    f.render_widget(Paragraph::new(Text::styled(heading, Style::default().fg(color_scheme.title))), chunks[1]);
+
+    let preview_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(3), Constraint::Length(4)])
+        .split(chunks[2]);
+
+    let delimiter = match import.delimiter() {
+        '\t' => String::from("tab"),
+        delimiter => format!("'{delimiter}'"),
+    };
+    let column = |column: Option<usize>| column.map_or(String::from("none"), |column| (column + 1).to_string());
+    let target = match &import.target {
+        ImportTarget::NewDeck(name) => format!("new deck {name}"),
+        ImportTarget::ExistingDeck(index) => format!("deck {}", app.decks.get(*index).map_or("", |deck| deck.name.as_str())),
+    };
+    let options_text = vec![
+        Line::from(format!(
+            "delimiter: {}{}   header: {}   columns: {}",
+            delimiter,
+            if import.options.delimiter.is_none() { " (detected)" } else { "" },
+            if import.options.has_header { "yes" } else { "no" },
+            import.plan.column_count,
+        )),
+        Line::from(format!(
+            "front: column {}   back: column {}   tags: column {}",
+            column(Some(import.options.front_column)),
+            column(Some(import.options.back_column)),
+            column(import.options.tags_column),
+        )),
+        Line::from(format!("into: {target}")),
+    ];
+    let options_paragraph = Paragraph::new(options_text)
+        .style(Style::default().fg(color_scheme.normal))
+        .block(Block::default().borders(Borders::BOTTOM));
+    f.render_widget(options_paragraph, preview_chunks[0]);
+
+    let list_items: Vec<ListItem> = import
+        .plan
+        .cards
+        .iter()
+        .map(|(front, back, tags)| {
+            let mut format = format!("{front} | {back}");
+            if !tags.is_empty() {
+                format = format!("{format} [{}]", tags.iter().cloned().collect::<Vec<_>>().join(" "));
+            }
+            ListItem::new(Line::from(Span::styled(format, Style::default().fg(color_scheme.selected))))
+        })
+        .collect();
+    f.render_widget(List::new(list_items), preview_chunks[1]);
+
+    let lines_with = |reasons: &[SkipReason]| {
+        import
+            .plan
+            .skipped
+            .iter()
+            .filter(|(_, reason)| reasons.contains(reason))
+            .map(|(line, _)| line.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let summary_text = vec![
+        Line::from(Span::styled(
+            format!(
+                "{} cards will be imported, {} rows skipped ({} duplicates)",
+                import.plan.cards.len(),
+                import.plan.skipped.len(),
+                import.plan.duplicate_count(),
+            ),
+            Style::default().fg(color_scheme.title),
+        )),
+        Line::from(Span::styled(
+            format!("incomplete rows on lines: {}", lines_with(&[SkipReason::MissingColumn, SkipReason::EmptyField])),
+            Style::default().fg(color_scheme.warning),
+        )),
+        Line::from(Span::styled(
+            format!("duplicate rows on lines: {}", lines_with(&[SkipReason::Duplicate])),
+            Style::default().fg(color_scheme.warning),
+        )),
+    ];
+    let summary_paragraph = Paragraph::new(summary_text).block(Block::default().borders(Borders::TOP));
+    f.render_widget(summary_paragraph, preview_chunks[2]);
 }
 
 fn display_learning_mode(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: ColorScheme) {
@@ -343,16 +971,31 @@ fn display_learning_mode(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_sch
         .constraints([Constraint::Percentage(33), Constraint::Percentage(33), Constraint::Percentage(33)])
         .split(learning_area_chunks[2]);
 
+    let Some(card) = app.learning_card() else {
+        let nothing_due_paragraph = Paragraph::new(Text::styled(
+            if app.custom_study.is_some() {
+                "Custom study finished, every matching card was answered!"
+            } else if app.tag_filter.is_empty() {
+                "No cards are due in this deck, come back later!"
+            } else {
+                "No cards with these tags are due in this deck, come back later!"
+            },
+            Style::default().fg(color_scheme.title),
+        )).alignment(Alignment::Center);
+        f.render_widget(nothing_due_paragraph, learning_area_chunks[0]);
+        return;
+    };
+
     let front_card_block = Block::default().borders(Borders::NONE);
     let back_card_block = Block::default().borders(Borders::ALL);
 
     let front_text = Text::styled(
-        app.decks[app.selected_index.unwrap_or_default()].cards[app.card_currently_learning.unwrap_or_default()].front.clone(),
+        card.question(),
         Style::default().fg(color_scheme.normal).add_modifier(ratatui::style::Modifier::BOLD),
     );
 
     let back_text = Text::styled(
-        app.decks[app.selected_index.unwrap_or_default()].cards[app.card_currently_learning.unwrap_or_default()].back.clone(),
+        card.answer(),
         Style::default().fg(color_scheme.normal),
     );
 
@@ -367,24 +1010,75 @@ fn display_learning_mode(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_sch
         .wrap(Wrap { trim: false });
 
     let command_incorrect_paragraph = Paragraph::new(Text::styled(
-        "(h) incorrect",
+        format!("({}) incorrect", app.keymap.key_name(Action::GradeIncorrect)),
         Style::default().fg(color_scheme.warning),
     )).alignment(Alignment::Center);
     let command_correct_paragraph = Paragraph::new(Text::styled(
-        "(j) correct",
+        format!("({}) correct", app.keymap.key_name(Action::GradeCorrect)),
         Style::default().fg(color_scheme.title),
     )).alignment(Alignment::Center);
     let command_easy_paragraph = Paragraph::new(Text::styled(
-        "(k) easy",
-        Style::default().fg(Color::Green),
+        format!("({}) easy", app.keymap.key_name(Action::GradeEasy)),
+        Style::default().fg(color_scheme.success),
     )).alignment(Alignment::Center);
     let command_show_back_paragraph = Paragraph::new(Text::styled(
-        "press (ENTER) to reveal the back of the card",
+        format!("press ({}) to reveal the back of the card", app.keymap.key_name(Action::ShowAnswer)),
         Style::default().fg(color_scheme.title),
     )).alignment(Alignment::Center);
 
     f.render_widget(card_name_paragraph, learning_area_chunks[0]);
-    
+
+    if app.typing_answer() {
+        let answer_block = Block::default().title("Type the answer").borders(Borders::ALL);
+        display_text_input(f, &app.answer_input, answer_block, learning_area_chunks[1], true);
+        let command_check_paragraph = Paragraph::new(Text::styled(
+            "press (ENTER) to check your answer",
+            Style::default().fg(color_scheme.title),
+        )).alignment(Alignment::Center);
+        f.render_widget(command_check_paragraph, commands_chunks[1]);
+        return;
+    }
+    if let Some(multiple_choice) = &app.multiple_choice {
+        let choice_items: Vec<ListItem> = multiple_choice
+            .choices
+            .iter()
+            .enumerate()
+            .map(|(index, choice)| {
+                let style = match multiple_choice.picked {
+                    Some(_) if index == multiple_choice.correct => Style::default().fg(color_scheme.success).add_modifier(Modifier::BOLD),
+                    Some(picked) if index == picked => Style::default().fg(color_scheme.warning).add_modifier(Modifier::CROSSED_OUT),
+                    _ => Style::default().fg(color_scheme.normal),
+                };
+                ListItem::new(Line::from(Span::styled(format!("({}) {}", index + 1, choice.replace('\n', " ")), style)))
+            })
+            .collect();
+        let choices_list = List::new(choice_items).block(Block::default().title("Pick the answer").borders(Borders::ALL));
+        f.render_widget(choices_list, learning_area_chunks[1]);
+
+        let (command, style) = match multiple_choice.guess() {
+            None => (String::from("press the number of the right answer"), Style::default().fg(color_scheme.title)),
+            Some(Guess::Correct) => (String::from("correct! press (ENTER) to continue"), Style::default().fg(color_scheme.success)),
+            Some(_) => (
+                format!("incorrect, the answer is ({}). press (ENTER) to continue", multiple_choice.correct + 1),
+                Style::default().fg(color_scheme.warning),
+            ),
+        };
+        f.render_widget(Paragraph::new(Text::styled(command, style)).alignment(Alignment::Center), learning_area_chunks[2]);
+        return;
+    }
+    if let Some(check) = app.answer_check() {
+        let options = app.decks[app.selected_index.unwrap_or_default()].answer_options;
+        let answer_check_paragraph = Paragraph::new(answer_check_text(&check, card, options, color_scheme))
+            .block(Block::default().borders(Borders::ALL))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: false });
+        f.render_widget(answer_check_paragraph, learning_area_chunks[1]);
+        f.render_widget(command_incorrect_paragraph, commands_chunks[0]);
+        f.render_widget(command_correct_paragraph, commands_chunks[1]);
+        f.render_widget(command_easy_paragraph, commands_chunks[2]);
+        return;
+    }
+
     if let Some(face_showing) = &app.face_showing {
         match face_showing {
             CardFace::CardFront => {
@@ -400,22 +1094,205 @@ fn display_learning_mode(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_sch
     }
 }
 
-fn display_exit_screen(f: &mut Frame, color_scheme: ColorScheme) {
+/// the typed answer with what was missing and what was extra coloured in, followed by the full answer and the suggested grade
+fn answer_check_text(check: &AnswerCheck, card: &Card, options: AnswerOptions, color_scheme: ColorScheme) -> Text<'static> {
+    let mut lines = vec![Line::default()];
+    for part in &check.diff {
+        let style = match part.kind {
+            DiffKind::Same => Style::default().fg(color_scheme.normal),
+            DiffKind::Missing => Style::default().fg(color_scheme.success).add_modifier(Modifier::UNDERLINED),
+            DiffKind::Extra => Style::default().fg(color_scheme.warning).add_modifier(Modifier::CROSSED_OUT),
+        };
+        // spans can't contain line breaks, so every line of the answer gets its own line
+        for (index, text) in part.text.split('\n').enumerate() {
+            if index > 0 {
+                lines.push(Line::default());
+            }
+            if let Some(line) = lines.last_mut() {
+                line.spans.push(Span::styled(text.to_string(), style));
+            }
+        }
+    }
+
+    let suggested = match check.suggested {
+        Guess::Easy => "easy",
+        Guess::Correct => "correct",
+        Guess::Incorrect | Guess::None => "incorrect",
+    };
+    let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+    lines.push(Line::default());
+    lines.extend(Text::styled(card.answer(), Style::default().fg(color_scheme.normal)).lines);
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled(
+        format!("{} differences, suggested grade: {} (ENTER to accept)", check.distance, suggested),
+        Style::default().fg(color_scheme.title),
+    )));
+    lines.push(Line::from(Span::styled(
+        format!(
+            "ignore case: {} / ignore accents: {} / ignore whitespace: {}",
+            on_off(options.ignore_case),
+            on_off(options.ignore_accents),
+            on_off(options.ignore_whitespace)
+        ),
+        Style::default().fg(color_scheme.normal),
+    )));
+    Text::from(lines)
+}
+
+fn display_statistics(app: &App, f: &mut Frame, chunks: Rc<[Rect]>, color_scheme: ColorScheme) {
+    let statistics = app.statistics();
+
+    let heading = match app.statistics_deck.and_then(|index| app.decks.get(index)) {
+        Some(deck) => format!("Statistics for deck {}", deck.name),
+        None => String::from("Statistics for all decks"),
+    };
+    let summary = format!(
+        "{} - {} cards, {} reviews ({} today), {} spent learning",
+        heading,
+        statistics.total_cards,
+        statistics.total_reviews,
+        statistics.reviews_today,
+        format_duration(statistics.total_time_spent_ms),
+    );
+    f.render_widget(
+        Paragraph::new(Text::styled(summary, Style::default().fg(color_scheme.title))),
+        chunks[1],
+    );
+
+    let statistics_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Length(6), Constraint::Min(6)])
+        .split(chunks[2]);
+    let sparkline_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(statistics_chunks[1]);
+    let bar_chart_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(statistics_chunks[2]);
+
+    let retention_gauge = match statistics.retention {
+        Some(retention) => Gauge::default()
+            .ratio(retention)
+            .label(format!("{:.0}%", retention * 100.0)),
+        None => Gauge::default().ratio(0.0).label("no reviews yet"),
+    }
+    .block(Block::default().title(format!("Retention (last {} days)", STATS_DAYS)).borders(Borders::ALL))
+    .gauge_style(Style::default().fg(color_scheme.success));
+    f.render_widget(retention_gauge, statistics_chunks[0]);
+
+    let reviews_sparkline = Sparkline::default()
+        .block(Block::default().title(format!("Reviews per day (last {} days)", STATS_DAYS)).borders(Borders::ALL))
+        .data(&statistics.reviews_per_day)
+        .style(Style::default().fg(color_scheme.normal));
+    f.render_widget(reviews_sparkline, sparkline_chunks[0]);
+
+    let time_spent_sparkline = Sparkline::default()
+        .block(Block::default().title(format!("Time spent per day (last {} days)", STATS_DAYS)).borders(Borders::ALL))
+        .data(&statistics.time_spent_per_day)
+        .style(Style::default().fg(color_scheme.highlight));
+    f.render_widget(time_spent_sparkline, sparkline_chunks[1]);
+
+    let forecast_labels: Vec<String> = (0..STATS_DAYS).map(|day| day.to_string()).collect();
+    let forecast_data: Vec<(&str, u64)> = forecast_labels
+        .iter()
+        .map(String::as_str)
+        .zip(statistics.due_forecast.iter().copied())
+        .collect();
+    let forecast_chart = BarChart::default()
+        .block(Block::default().title("Due in the next days").borders(Borders::ALL))
+        .data(&forecast_data)
+        .bar_width(2)
+        .bar_gap(0)
+        .bar_style(Style::default().fg(color_scheme.normal))
+        .value_style(Style::default().fg(color_scheme.selected_box_text).bg(color_scheme.normal));
+    f.render_widget(forecast_chart, bar_chart_chunks[0]);
+
+    let interval_chart = BarChart::default()
+        .block(Block::default().title("Intervals").borders(Borders::ALL))
+        .data(&statistics.interval_distribution)
+        .bar_width(4)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(color_scheme.highlight))
+        .value_style(Style::default().fg(color_scheme.selected_box_text).bg(color_scheme.highlight));
+    f.render_widget(interval_chart, bar_chart_chunks[1]);
+}
+
+/// the themes with the selected one drawn as a preview, and a sample of each colour of it
+fn display_theme_picker(app: &App, f: &mut Frame, color_scheme: ColorScheme) {
+    let area = centered_rect(60, 60, f.size());
+    f.render_widget(Clear, area);
+    let popup_block = Block::default()
+        .title(format!("Pick a theme ({})", app.color_support.name()))
+        .borders(Borders::ALL)
+        .style(Style::default().bg(color_scheme.selected_box_text).fg(color_scheme.selected_box));
+    let inner = popup_block.inner(area);
+    f.render_widget(popup_block, area);
+
+    let picker_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(inner);
+
+    let theme_items: Vec<ListItem> = app
+        .themes
+        .iter()
+        .enumerate()
+        .map(|(index, theme)| {
+            if index == app.theme_index {
+                ListItem::new(Line::from(Span::styled(format!("-> {}", theme.name), Style::default().fg(color_scheme.selected))))
+            } else {
+                ListItem::new(Line::from(Span::styled(format!("   {}", theme.name), Style::default().fg(color_scheme.normal))))
+            }
+        })
+        .collect();
+    f.render_widget(List::new(theme_items), picker_chunks[0]);
+
+    let sample = |label: &'static str, color| Line::from(Span::styled(label, Style::default().fg(color)));
+    let sample_lines = vec![
+        sample("Title", color_scheme.title),
+        sample("Normal text", color_scheme.normal),
+        sample("Selected item", color_scheme.selected),
+        sample("Highlight", color_scheme.highlight),
+        sample("Correct answer", color_scheme.success),
+        sample("Warning", color_scheme.warning),
+    ];
+    f.render_widget(Paragraph::new(sample_lines).block(Block::default().borders(Borders::LEFT)), picker_chunks[1]);
+}
+
+fn display_exit_screen(app: &App, f: &mut Frame, color_scheme: ColorScheme) {
     f.render_widget(Clear, f.size()); //this clears the entire screen and anything already drawn
     let popup_block = Block::default()
-        .title("Y/N")
+        .title("Export before quitting?")
         .borders(Borders::NONE)
         .style(Style::default().bg(color_scheme.selected_box_text).fg(color_scheme.selected_box));
 
-    let exit_text = Text::styled(
-        "Would you like to output the buffer as json? (y/n)",
+    let scope = match app.decks_to_export().as_slice() {
+        [deck] if !app.export_all_decks => format!("deck {}", deck.name),
+        decks => format!("all {} decks", decks.len()),
+    };
+    let mut exit_lines = vec![
+        Line::from(Span::styled(format!("Exporting {scope}, press (a) to switch"), Style::default().fg(color_scheme.title))),
+        Line::from(""),
+    ];
+    for (index, format) in ExportFormat::ALL.iter().enumerate() {
+        exit_lines.push(Line::from(Span::styled(
+            format!("({}) {}", index + 1, format.name()),
+            Style::default().fg(color_scheme.normal),
+        )));
+    }
+    exit_lines.push(Line::from(""));
+    exit_lines.push(Line::from(Span::styled(
+        "(n) quit without exporting / (ESC) back",
         Style::default().fg(color_scheme.warning),
-    );
+    )));
+
     // the `trim: false` will stop the text from being cut off when over the edge of the block
-    let exit_paragraph = Paragraph::new(exit_text)
+    let exit_paragraph = Paragraph::new(exit_lines)
         .block(popup_block)
         .wrap(Wrap { trim: false });
 
-    let area = centered_rect(60, 25, f.size());
+    let area = centered_rect(60, 50, f.size());
     f.render_widget(exit_paragraph, area);
-}
\ No newline at end of file
+}